@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AddrMod {
     None,
     Implied,
@@ -14,10 +14,24 @@ pub enum AddrMod {
     Absolute,
     AbsoluteX,
     AbsoluteY,
+    ZeroPageIndirect,
+    AbsoluteIndirectX,
+    ZeroPageRelative,
+    // 65C816 modes, the zero page modes double as its direct page modes
+    ImmediateWord,
+    AbsoluteLong,
+    AbsoluteLongX,
+    AbsoluteIndirectLong,
+    DirectIndirectLong,
+    DirectIndirectLongY,
+    StackRelative,
+    StackRelativeIndirectY,
+    RelativeLong,
+    BlockMove,
 }
 
 impl AddrMod {
-    pub fn bytes(&self) -> usize {
+    pub const fn bytes(&self) -> usize {
         match self {
             AddrMod::None => 0,
             AddrMod::Implied => 1,
@@ -33,6 +47,19 @@ impl AddrMod {
             AddrMod::Absolute => 3,
             AddrMod::AbsoluteX => 3,
             AddrMod::AbsoluteY => 3,
+            AddrMod::ZeroPageIndirect => 2,
+            AddrMod::AbsoluteIndirectX => 3,
+            AddrMod::ZeroPageRelative => 3,
+            AddrMod::ImmediateWord => 3,
+            AddrMod::AbsoluteLong => 4,
+            AddrMod::AbsoluteLongX => 4,
+            AddrMod::AbsoluteIndirectLong => 3,
+            AddrMod::DirectIndirectLong => 2,
+            AddrMod::DirectIndirectLongY => 2,
+            AddrMod::StackRelative => 2,
+            AddrMod::StackRelativeIndirectY => 2,
+            AddrMod::RelativeLong => 3,
+            AddrMod::BlockMove => 3,
         }
     }
 
@@ -52,6 +79,51 @@ impl AddrMod {
             AddrMod::Absolute => "".to_string(),
             AddrMod::AbsoluteX => "".to_string(),
             AddrMod::AbsoluteY => "".to_string(),
+            AddrMod::ZeroPageIndirect => "".to_string(),
+            AddrMod::AbsoluteIndirectX => "".to_string(),
+            AddrMod::ZeroPageRelative => "".to_string(),
+            AddrMod::ImmediateWord => "#".to_string(),
+            AddrMod::AbsoluteLong => "".to_string(),
+            AddrMod::AbsoluteLongX => "".to_string(),
+            AddrMod::AbsoluteIndirectLong => "".to_string(),
+            AddrMod::DirectIndirectLong => "".to_string(),
+            AddrMod::DirectIndirectLongY => "".to_string(),
+            AddrMod::StackRelative => "".to_string(),
+            AddrMod::StackRelativeIndirectY => "".to_string(),
+            AddrMod::RelativeLong => "".to_string(),
+            AddrMod::BlockMove => "".to_string(),
+        }
+    }
+
+    pub fn format(&self, operands: &[u8]) -> String {
+        let word = || u16::from_le_bytes([operands[0], operands[1]]);
+        let long = || u32::from_le_bytes([operands[0], operands[1], operands[2], 0]);
+        match self {
+            AddrMod::None | AddrMod::Implied | AddrMod::Accumulator => "".to_string(),
+            AddrMod::Immediate => format!("#${:02x}", operands[0]),
+            AddrMod::ZeroPage | AddrMod::Relative => format!("${:02x}", operands[0]),
+            AddrMod::ZeroPageX => format!("${:02x},X", operands[0]),
+            AddrMod::ZeroPageY => format!("${:02x},Y", operands[0]),
+            AddrMod::IndirectX => format!("(${:02x},X)", operands[0]),
+            AddrMod::IndirectY => format!("(${:02x}),Y", operands[0]),
+            AddrMod::ZeroPageIndirect => format!("(${:02x})", operands[0]),
+            AddrMod::Indirect => format!("(${:04x})", word()),
+            AddrMod::Absolute => format!("${:04x}", word()),
+            AddrMod::AbsoluteX => format!("${:04x},X", word()),
+            AddrMod::AbsoluteY => format!("${:04x},Y", word()),
+            AddrMod::AbsoluteIndirectX => format!("(${:04x},X)", word()),
+            AddrMod::ZeroPageRelative => format!("${:02x},${:02x}", operands[0], operands[1]),
+            AddrMod::ImmediateWord => format!("#${:04x}", word()),
+            AddrMod::AbsoluteLong => format!("${:06x}", long()),
+            AddrMod::AbsoluteLongX => format!("${:06x},X", long()),
+            AddrMod::AbsoluteIndirectLong => format!("[${:04x}]", word()),
+            AddrMod::DirectIndirectLong => format!("[${:02x}]", operands[0]),
+            AddrMod::DirectIndirectLongY => format!("[${:02x}],Y", operands[0]),
+            AddrMod::StackRelative => format!("${:02x},S", operands[0]),
+            AddrMod::StackRelativeIndirectY => format!("(${:02x},S),Y", operands[0]),
+            AddrMod::RelativeLong => format!("${:04x}", word()),
+            // Encoded destination bank first, written source bank first
+            AddrMod::BlockMove => format!("${:02x},${:02x}", operands[1], operands[0]),
         }
     }
 }
@@ -1,326 +1,1521 @@
 use super::instructions::Instruction;
 use crate::addrmod::AddrMod;
+use crate::expr;
+use crate::memory::{Bus, MEMORY_SIZE};
+use crate::mnemonic::Mnemonic;
+use crate::optable;
+use crate::variant::{Variant, Widths};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::path::PathBuf;
 
 pub fn read_rom(filepath: &str) -> Vec<u8> {
-    let rom = fs::read(filepath).expect("Can't read rom");
-    rom
-}
-
-pub fn next_instruction(rom: &Vec<u8>, pc: u16) -> (Instruction, u16) {
-    let mut _pc = pc as usize;
-
-    let instruction = match rom[_pc] {
-        // ADC
-        0x69 => Instruction::adc(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0x65 => Instruction::adc(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x75 => Instruction::adc(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x6D => Instruction::adc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x7D => Instruction::adc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0x79 => Instruction::adc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0x61 => Instruction::adc(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0x71 => Instruction::adc(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // AND
-        0x29 => Instruction::and(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0x25 => Instruction::and(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x35 => Instruction::and(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x2D => Instruction::and(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x3D => Instruction::and(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0x39 => Instruction::and(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-        0x21 => Instruction::and(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0x31 => Instruction::and(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // ASL
-        0x0A => Instruction::asl(vec![], AddrMod::Accumulator),
-        0x06 => Instruction::asl(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x16 => Instruction::asl(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x0E => Instruction::asl(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x1E => Instruction::asl(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-
-        // BCC
-        0x90 => Instruction::bcc(vec![rom[_pc + 1]]),
-
-        // BSC
-        0xB0 => Instruction::bcs(vec![rom[_pc + 1]]),
-
-        // BEQ
-        0xF0 => Instruction::beq(vec![rom[_pc + 1]]),
-
-        // BIT
-        0x24 => Instruction::bit(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x2C => Instruction::bit(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-
-        // BMI
-        0x30 => Instruction::bmi(vec![rom[_pc + 1]]),
-
-        // BNE
-        0xD0 => Instruction::bne(vec![rom[_pc + 1]]),
-
-        // BPL
-        0x10 => Instruction::bpl(vec![rom[_pc + 1]]),
-
-        // BRK
-        0x00 => Instruction::brk(),
-
-        // BVC
-        0x50 => Instruction::bvc(vec![rom[_pc + 1]]),
-
-        // BVS
-        0x70 => Instruction::bvs(vec![rom[_pc + 1]]),
-
-        // CLC
-        0x18 => Instruction::clc(),
-
-        // CLD
-        0xD8 => Instruction::cld(),
-
-        // CLI
-        0x58 => Instruction::cli(),
-
-        // CLV
-        0xB8 => Instruction::clv(),
-
-        // CMP
-        0xC9 => Instruction::cmp(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0xC5 => Instruction::cmp(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xD5 => Instruction::cmp(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0xCD => Instruction::cmp(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0xDD => Instruction::cmp(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0xD9 => Instruction::cmp(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-        0xC1 => Instruction::cmp(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0xD1 => Instruction::cmp(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // CPX
-        0xE0 => Instruction::cpx(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0xE4 => Instruction::cpx(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xEC => Instruction::cpx(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-
-        // CPY
-        0xC0 => Instruction::cpy(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0xC4 => Instruction::cpy(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xCC => Instruction::cpy(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-
-        // DEC
-        0xC6 => Instruction::dec(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xD6 => Instruction::dec(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0xCE => Instruction::dec(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0xDE => Instruction::dec(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-
-        // DEX
-        0xCA => Instruction::dex(),
-
-        // DEY
-        0x88 => Instruction::dey(),
-
-        // EOR
-        0x49 => Instruction::eor(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0x45 => Instruction::eor(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x55 => Instruction::eor(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x4D => Instruction::eor(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x5D => Instruction::eor(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0x59 => Instruction::eor(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-        0x41 => Instruction::eor(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0x51 => Instruction::eor(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // INC
-        0xE6 => Instruction::inc(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xF6 => Instruction::inc(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0xEE => Instruction::inc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0xFE => Instruction::inc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-
-        // INX
-        0xE8 => Instruction::inx(),
-
-        // INY
-        0xC8 => Instruction::iny(),
-
-        // JMP
-        0x4C => Instruction::jmp(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x6C => Instruction::jmp(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Indirect),
-
-        // JSR
-        0x20 => Instruction::jsr(vec![rom[_pc + 1], rom[_pc + 2]]),
-
-        // LDA
-        0xA9 => Instruction::lda(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0xA5 => Instruction::lda(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xB5 => Instruction::lda(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0xAD => Instruction::lda(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0xBD => Instruction::lda(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0xB9 => Instruction::lda(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-        0xA1 => Instruction::lda(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0xB1 => Instruction::lda(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // LDX
-        0xA2 => Instruction::ldx(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0xA6 => Instruction::ldx(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xB6 => Instruction::ldx(vec![rom[_pc + 1]], AddrMod::ZeroPageY),
-        0xAE => Instruction::ldx(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0xBE => Instruction::ldx(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-
-        // LDY
-        0xA0 => Instruction::ldy(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0xA4 => Instruction::ldy(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xB4 => Instruction::ldy(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0xAC => Instruction::ldy(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0xBC => Instruction::ldy(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-
-        // LSR
-        0x4A => Instruction::lsr(vec![rom[_pc + 1]], AddrMod::Accumulator),
-        0x46 => Instruction::lsr(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x56 => Instruction::lsr(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x4E => Instruction::lsr(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x5E => Instruction::lsr(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-
-        // NOP
-        0xEA => Instruction::nop(),
-
-        // ORA
-        0x09 => Instruction::ora(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0x05 => Instruction::ora(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x15 => Instruction::ora(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x0D => Instruction::ora(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x1D => Instruction::ora(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0x19 => Instruction::ora(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-        0x01 => Instruction::ora(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0x11 => Instruction::ora(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // PHA
-        0x48 => Instruction::pha(),
-
-        // PHP
-        0x08 => Instruction::php(),
-
-        // PLA
-        0x68 => Instruction::pla(),
-
-        // PLP
-        0x28 => Instruction::plp(),
-
-        // ROL
-        0x2A => Instruction::rol(vec![rom[_pc + 1]], AddrMod::Accumulator),
-        0x26 => Instruction::rol(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x36 => Instruction::rol(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x2E => Instruction::rol(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x3E => Instruction::rol(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-
-        // ROR
-        0x6A => Instruction::ror(vec![rom[_pc + 1]], AddrMod::Accumulator),
-        0x66 => Instruction::ror(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x76 => Instruction::ror(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x6E => Instruction::ror(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x7E => Instruction::ror(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-
-        // RTI
-        0x40 => Instruction::rti(),
-
-        // RTS
-        0x60 => Instruction::rts(),
-
-        // SBC
-        0xE9 => Instruction::sbc(vec![rom[_pc + 1]], AddrMod::Immediate),
-        0xE5 => Instruction::sbc(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0xF5 => Instruction::sbc(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0xED => Instruction::sbc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0xFD => Instruction::sbc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0xF9 => Instruction::sbc(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-        0xE1 => Instruction::sbc(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0xF1 => Instruction::sbc(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // SEC
-        0x38 => Instruction::sec(),
-
-        // SED
-        0xF8 => Instruction::sed(),
-
-        // SEI
-        0x78 => Instruction::sei(),
-
-        // STA
-        0x85 => Instruction::sta(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x95 => Instruction::sta(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x8D => Instruction::sta(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-        0x9D => Instruction::sta(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteX),
-        0x99 => Instruction::sta(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::AbsoluteY),
-        0x81 => Instruction::sta(vec![rom[_pc + 1]], AddrMod::IndirectX),
-        0x91 => Instruction::sta(vec![rom[_pc + 1]], AddrMod::IndirectY),
-
-        // STX
-        0x86 => Instruction::stx(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x96 => Instruction::stx(vec![rom[_pc + 1]], AddrMod::ZeroPageY),
-        0x8E => Instruction::stx(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-
-        // STY
-        0x84 => Instruction::sty(vec![rom[_pc + 1]], AddrMod::ZeroPage),
-        0x94 => Instruction::sty(vec![rom[_pc + 1]], AddrMod::ZeroPageX),
-        0x8C => Instruction::sty(vec![rom[_pc + 1], rom[_pc + 2]], AddrMod::Absolute),
-
-        // TAX
-        0xAA => Instruction::tax(),
-
-        // TAY
-        0xA8 => Instruction::tay(),
-
-        // TSX
-        0xBA => Instruction::tsx(),
-
-        // TXA
-        0x8A => Instruction::txa(),
-
-        // TXS
-        0x9A => Instruction::txs(),
-
-        // TYA
-        0x98 => Instruction::tya(),
-
-        // UKN
-        _ => Instruction::ukn(),
-    };
+    fs::read(filepath).expect("Can't read rom")
+}
+
+pub fn next_instruction<B: Bus + ?Sized>(
+    memory: &B,
+    pc: u16,
+    variant: Variant,
+) -> (Instruction, u16) {
+    decode(&|addr| memory.peek(addr), pc, variant, Widths::default())
+}
 
-    let instruction_size = instruction.addr_mod.bytes();
-    (instruction, (_pc + instruction_size) as u16)
+// Decodes from any byte source, e.g. a CPU that remaps addresses on the way
+// to its bus. Widths only matter to the 65C816, whose immediate operands grow
+// with its registers.
+pub fn decode(
+    peek: &dyn Fn(u16) -> u8,
+    pc: u16,
+    variant: Variant,
+    widths: Widths,
+) -> (Instruction, u16) {
+    let opcode = optable::lookup(variant, peek(pc));
+    let (addr_mod, bytes) = opcode.sized(variant.is_65816() && widths.wide(opcode.mnemonic));
+    let operands = (1..bytes as u16)
+        .map(|offset| peek(pc.wrapping_add(offset)))
+        .collect();
+
+    let instruction = Instruction::from_opcode(opcode, operands, addr_mod);
+    (instruction, pc.wrapping_add(bytes as u16))
 }
 
-pub fn disassemble(rom: &Vec<u8>, show_line_number: bool) -> String {
-    let mut pc = 0;
+pub fn disassemble<B: Bus + ?Sized>(
+    memory: &B,
+    start: u16,
+    end: u32,
+    variant: Variant,
+    show_line_number: bool,
+) -> String {
+    disassemble_from(
+        &|addr| memory.peek(addr),
+        start,
+        end,
+        variant,
+        Widths::default(),
+        show_line_number,
+    )
+}
+
+pub fn disassemble_from(
+    peek: &dyn Fn(u16) -> u8,
+    start: u16,
+    end: u32,
+    variant: Variant,
+    mut widths: Widths,
+    show_line_number: bool,
+) -> String {
+    let mut pc = start as u32;
     let mut dis_asm = String::new();
     let mut counter: u32 = 0;
 
-    while (pc as usize) < rom.len() {
-        let result = crate::assembler::next_instruction(&rom, pc);
-        pc = result.1;
+    // The end is exclusive and can be 0x10000, to take in the byte at $FFFF
+    while pc < end {
+        let result = decode(peek, pc as u16, variant, widths);
+        // Follow REP and SEP so the operands after them get the right width
+        match result.0.mnemonic {
+            Mnemonic::Rep => {
+                widths.wide_a |= result.0.operands[0] & 0x20 != 0;
+                widths.wide_index |= result.0.operands[0] & 0x10 != 0;
+            }
+            Mnemonic::Sep => {
+                widths.wide_a &= result.0.operands[0] & 0x20 == 0;
+                widths.wide_index &= result.0.operands[0] & 0x10 == 0;
+            }
+            _ => (),
+        }
+        // Unknown opcodes have no length, step over the byte to keep going
+        pc += result.0.addr_mod.bytes().max(1) as u32;
         if show_line_number {
             counter += 1;
             dis_asm.push_str(&format!("{:>3}: ", counter));
         }
         dis_asm.push_str(&fmt_dasm(result.0));
-        dis_asm.push_str("\n");
+        dis_asm.push('\n');
     }
     dis_asm
 }
 
 pub fn fmt_dasm(instruction: Instruction) -> String {
-    let opcode = instruction.opcode;
-    let operands = instruction.operands;
-    let mut code = opcode.to_string();
-    let addr_mod = instruction.addr_mod;
-
-    if addr_mod == AddrMod::Absolute
-        || addr_mod == AddrMod::AbsoluteX
-        || addr_mod == AddrMod::AbsoluteY
-    {
-        code.push_str(&format!(
-            " {}${:02x}{:02x}",
-            addr_mod.indicator(),
-            operands[1],
-            operands[0]
-        ));
+    let operands = instruction.addr_mod.format(&instruction.operands);
+    if operands.is_empty() {
+        instruction.mnemonic.to_string()
+    } else {
+        format!("{} {}", instruction.mnemonic, operands)
+    }
+}
+
+// File and line an error was found on. Source given as text has no file
+// name, line 0 stands for the file as a whole.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub file: String,
+    pub line: usize,
+    pub msg: String,
+    // Macro calls that led to the line, the innermost first
+    pub expansions: Vec<Expansion>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+}
+
+fn location(file: &str, line: usize) -> String {
+    match (file.is_empty(), line) {
+        (true, _) => format!("line {}", line),
+        (false, 0) => file.to_string(),
+        (false, _) => format!("{}:{}", file, line),
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", location(&self.file, self.line), self.msg)?;
+        // A macro calling itself shows up once with a count
+        let mut idx = 0;
+        while idx < self.expansions.len() {
+            let expansion = &self.expansions[idx];
+            let repeats = self.expansions[idx..]
+                .iter()
+                .take_while(|other| *other == expansion)
+                .count();
+            write!(
+                f,
+                "\n    in macro '{}' called at {}",
+                expansion.name,
+                location(&expansion.file, expansion.line)
+            )?;
+            if repeats > 1 {
+                write!(f, " ({} times)", repeats)?;
+            }
+            idx += repeats;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Program {
+    pub origin: u16,
+    pub image: Vec<u8>,
+    pub symbols: HashMap<String, i64>,
+    // Every assembled instruction with its address
+    pub instructions: Vec<(u16, Instruction)>,
+}
+
+// Operand syntax, each form assembles to one of a few addressing modes
+// depending on the instruction and the size of its value
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand<'a> {
+    None,
+    Accumulator,
+    Immediate(&'a str),
+    Direct(&'a str),
+    DirectX(&'a str),
+    DirectY(&'a str),
+    Indirect(&'a str),
+    IndirectX(&'a str),
+    IndirectY(&'a str),
+    // zp,target for BBR/BBS and source,destination for the block moves
+    Pair(&'a str, &'a str),
+    // 65C816 only
+    IndirectLong(&'a str),
+    IndirectLongY(&'a str),
+    Stack(&'a str),
+    StackIndirectY(&'a str),
+}
+
+impl<'a> Operand<'a> {
+    fn parse(text: &'a str) -> Operand<'a> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Operand::None;
+        }
+        if text.eq_ignore_ascii_case("a") {
+            return Operand::Accumulator;
+        }
+        if let Some(expr) = text.strip_prefix('#') {
+            return Operand::Immediate(expr.trim());
+        }
+        if let Some(inner) = enclosed(text) {
+            return match split_index(inner) {
+                Some((expr, "X" | "x")) => Operand::IndirectX(expr),
+                _ if text.starts_with('[') => Operand::IndirectLong(inner.trim()),
+                _ => Operand::Indirect(inner.trim()),
+            };
+        }
+        match split_index(text) {
+            Some((expr, "X" | "x")) => Operand::DirectX(expr),
+            Some((expr, "S" | "s")) => Operand::Stack(expr),
+            Some((expr, "Y" | "y")) => match enclosed(expr) {
+                Some(inner) if expr.starts_with('[') => Operand::IndirectLongY(inner.trim()),
+                Some(inner) => match split_index(inner) {
+                    Some((inner, "S" | "s")) => Operand::StackIndirectY(inner),
+                    _ => Operand::IndirectY(inner.trim()),
+                },
+                None => Operand::DirectY(expr),
+            },
+            Some((first, second)) => Operand::Pair(first, second),
+            None => Operand::Direct(text),
+        }
+    }
+
+    fn exprs(&self) -> (Option<&'a str>, Option<&'a str>) {
+        match *self {
+            Operand::None | Operand::Accumulator => (None, None),
+            Operand::Pair(first, second) => (Some(first), Some(second)),
+            Operand::Immediate(expr)
+            | Operand::Direct(expr)
+            | Operand::DirectX(expr)
+            | Operand::DirectY(expr)
+            | Operand::Indirect(expr)
+            | Operand::IndirectX(expr)
+            | Operand::IndirectY(expr)
+            | Operand::IndirectLong(expr)
+            | Operand::IndirectLongY(expr)
+            | Operand::Stack(expr)
+            | Operand::StackIndirectY(expr) => (Some(expr), None),
+        }
+    }
+
+    // Addressing modes to try in order. A value that is not known yet may
+    // turn out to be anywhere, so it only gets zero page when nothing else
+    // fits.
+    fn modes(&self, value: Option<i64>) -> Vec<AddrMod> {
+        let zero_page = value.is_some_and(|val| (0..=0xff).contains(&val));
+        let long = value.is_some_and(|val| val > 0xffff);
+        let sized = |zp: AddrMod, abs: AddrMod, abs_long: Option<AddrMod>| {
+            let mut modes = vec![];
+            if zero_page {
+                modes.push(zp);
+            }
+            if !long {
+                modes.push(abs);
+            }
+            modes.extend(abs_long);
+            if value.is_none() {
+                modes.push(zp);
+            }
+            modes
+        };
+        match self {
+            Operand::None => vec![AddrMod::Implied, AddrMod::Accumulator],
+            Operand::Accumulator => vec![AddrMod::Accumulator],
+            Operand::Immediate(_) => vec![AddrMod::Immediate],
+            Operand::Direct(_) => {
+                let mut modes = vec![AddrMod::Relative, AddrMod::RelativeLong];
+                modes.extend(sized(
+                    AddrMod::ZeroPage,
+                    AddrMod::Absolute,
+                    Some(AddrMod::AbsoluteLong),
+                ));
+                modes
+            }
+            Operand::DirectX(_) => sized(
+                AddrMod::ZeroPageX,
+                AddrMod::AbsoluteX,
+                Some(AddrMod::AbsoluteLongX),
+            ),
+            Operand::DirectY(_) => sized(AddrMod::ZeroPageY, AddrMod::AbsoluteY, None),
+            Operand::Indirect(_) => sized(AddrMod::ZeroPageIndirect, AddrMod::Indirect, None),
+            Operand::IndirectX(_) => sized(AddrMod::IndirectX, AddrMod::AbsoluteIndirectX, None),
+            Operand::IndirectY(_) => vec![AddrMod::IndirectY],
+            Operand::Pair(_, _) => vec![AddrMod::ZeroPageRelative, AddrMod::BlockMove],
+            Operand::IndirectLong(_) => sized(
+                AddrMod::DirectIndirectLong,
+                AddrMod::AbsoluteIndirectLong,
+                None,
+            ),
+            Operand::IndirectLongY(_) => vec![AddrMod::DirectIndirectLongY],
+            Operand::Stack(_) => vec![AddrMod::StackRelative],
+            Operand::StackIndirectY(_) => vec![AddrMod::StackRelativeIndirectY],
+        }
+    }
+}
+
+// The inside of an operand wrapped in one pair of parentheses or brackets
+fn enclosed(text: &str) -> Option<&str> {
+    let close = match text.chars().next()? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    let end = top_level(text).find(|(_, ch)| *ch == close)?.0;
+    (end == text.len() - 1).then(|| &text[1..end])
+}
+
+// Splits "expr,X" at its last comma outside of brackets
+fn split_index(text: &str) -> Option<(&str, &str)> {
+    let comma = top_level(text).filter(|(_, ch)| *ch == ',').last()?.0;
+    Some((text[..comma].trim(), text[comma + 1..].trim()))
+}
+
+// Characters outside of brackets and quotes along with their byte offsets,
+// closing brackets at the outermost level included
+fn top_level(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut depth = 0;
+    unquoted(text).filter(move |(_, ch)| match ch {
+        '(' | '[' => {
+            depth += 1;
+            false
+        }
+        ')' | ']' => {
+            depth -= 1;
+            depth == 0
+        }
+        _ => depth == 0,
+    })
+}
+
+// Characters outside of string and character literals
+fn unquoted(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    text.char_indices().filter(move |(_, ch)| {
+        match (quote, ch) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(open), _) if open == *ch => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(*ch),
+            (None, _) => return true,
+        }
+        false
+    })
+}
+
+fn strip_comment(line: &str) -> &str {
+    match unquoted(line).find(|(_, ch)| *ch == ';') {
+        Some((idx, _)) => &line[..idx],
+        None => line,
+    }
+}
+
+// Comma separated directive arguments
+fn split_args(text: &str) -> Vec<&str> {
+    let mut args = vec![];
+    let mut start = 0;
+    for (idx, _) in top_level(text).filter(|(_, ch)| *ch == ',') {
+        args.push(text[start..idx].trim());
+        start = idx + 1;
+    }
+    args.push(text[start..].trim());
+    args
+}
+
+// The bytes of a double quoted string with C style escapes
+fn parse_string(text: &str) -> Result<Vec<u8>, String> {
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .filter(|_| text.len() >= 2)
+        .ok_or(format!("Invalid string {}", text))?;
+    let mut bytes = vec![];
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        let ch = match ch {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some(ch @ ('\\' | '"' | '\'')) => ch,
+                _ => return Err(format!("Invalid escape in string {}", text)),
+            },
+            ch => ch,
+        };
+        if !ch.is_ascii() {
+            return Err(format!("Non-ASCII character '{}' in string", ch));
+        }
+        bytes.push(ch as u8);
+    }
+    Ok(bytes)
+}
+
+fn to_byte(val: i64) -> Result<u8, String> {
+    if (-0x80..=0xff).contains(&val) {
+        Ok(val as u8)
+    } else {
+        Err(format!("Value {} does not fit in a byte", val))
+    }
+}
+
+fn to_word(val: i64) -> Result<[u8; 2], String> {
+    if (-0x8000..=0xffff).contains(&val) {
+        Ok((val as u16).to_le_bytes())
+    } else {
+        Err(format!("Value {} does not fit in a word", val))
+    }
+}
+
+// "NAME = expr" defines a constant, "* = expr" moves the program counter
+fn split_assignment(line: &str) -> Option<(&str, &str)> {
+    let (name, expr) = line.split_once('=')?;
+    let name = name.trim();
+    let assignment = !expr.starts_with('=') && (name == "*" || expr::is_identifier(name));
+    assignment.then_some((name, expr.trim()))
+}
+
+// A label is anything ending in a colon, or a word in the first column
+// that isn't a mnemonic, directive or macro
+fn split_label<'a>(line: &'a str, is_command: &dyn Fn(&str) -> bool) -> (Option<&'a str>, &'a str) {
+    let trimmed = line.trim_start();
+    let end = trimmed
+        .find(|ch: char| ch.is_whitespace() || ch == ':')
+        .unwrap_or(trimmed.len());
+    let (word, rest) = trimmed.split_at(end);
+    if let Some(rest) = rest.strip_prefix(':') {
+        return (Some(word), rest);
+    }
+    let first_column = !line.starts_with(char::is_whitespace);
+    if first_column && !word.is_empty() && !is_command(word) {
+        (Some(word), rest)
     } else {
-        for operand in operands {
-            code.push_str(&format!(" {}${:0>2x}", addr_mod.indicator(), operand));
+        (None, trimmed)
+    }
+}
+
+// The directive a line starts with, lowercased, and its arguments
+fn directive_of(line: &str) -> Option<(String, &str)> {
+    let line = strip_comment(line).trim();
+    if !line.starts_with('.') {
+        return None;
+    }
+    let (word, args) = line.split_at(line.find(char::is_whitespace).unwrap_or(line.len()));
+    Some((word.to_ascii_lowercase(), args.trim()))
+}
+
+// Text of a string argument, which parse_string keeps to ASCII
+fn message(text: &str) -> Result<String, String> {
+    Ok(String::from_utf8_lossy(&parse_string(text)?).into_owned())
+}
+
+// Number of lines up to the .endmacro closing a definition, definitions
+// inside it included
+fn macro_end(lines: &[&str]) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, line) in lines.iter().enumerate() {
+        match directive_of(line) {
+            Some((directive, _)) if directive == ".macro" => depth += 1,
+            Some((directive, _)) if directive == ".endmacro" && depth == 0 => return Some(idx),
+            Some((directive, _)) if directive == ".endmacro" => depth -= 1,
+            _ => (),
         }
     }
+    None
+}
+
+// Expansions deeper than this are taken for a macro calling itself
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+#[derive(Debug, Clone)]
+struct Macro {
+    params: Vec<String>,
+    body: Vec<String>,
+    // Where the .macro line is
+    file: String,
+    line: usize,
+}
+
+// Puts the arguments in place of the parameters and gives every @label a
+// name of its own in each expansion. Strings are left as they are.
+fn substitute(line: &str, params: &[String], args: &[&str], expansion: usize) -> String {
+    let mut words: Vec<(usize, usize)> = vec![];
+    for (idx, ch) in unquoted(line).filter(|(_, ch)| expr::is_symbol_char(*ch)) {
+        match words.last_mut() {
+            Some((_, end)) if *end == idx => *end += ch.len_utf8(),
+            _ => words.push((idx, idx + ch.len_utf8())),
+        }
+    }
+
+    let mut text = String::new();
+    let mut copied = 0;
+    for (start, end) in words {
+        let word = &line[start..end];
+        // Digits after a $ are a hex number, not a parameter
+        if word.starts_with(|ch: char| ch.is_ascii_digit()) || line[..start].ends_with('$') {
+            continue;
+        }
+        let replacement = match params.iter().position(|param| param == word) {
+            Some(idx) => args[idx].to_string(),
+            None if word.starts_with('@') => format!("{}@{}", word, expansion),
+            None => continue,
+        };
+        text.push_str(&line[copied..start]);
+        text.push_str(&replacement);
+        copied = end;
+    }
+    text.push_str(&line[copied..]);
+    text
+}
 
-    code
+// One .if block. Its lines are assembled while active, and once a branch is
+// taken the .elseif and .else branches after it are skipped.
+#[derive(Debug, Clone)]
+struct Cond {
+    active: bool,
+    taken: bool,
+    // False inside a block that is skipped as a whole
+    outer: bool,
+    seen_else: bool,
+    // Where the .if line is
+    file: String,
+    line: usize,
+}
+
+// Two passes over the source: the first one sizes every instruction to find
+// the label addresses, the second one encodes them
+struct Assembler {
+    variant: Variant,
+    origin: u16,
+    pc: u32,
+    widths: Widths,
+    final_pass: bool,
+    symbols: HashMap<String, i64>,
+    defined: HashSet<String>,
+    // Addressing mode of every instruction as sized in the first pass, the
+    // second pass keeps them so forward references can't move any label
+    modes: Vec<Option<AddrMod>>,
+    count: usize,
+    // Program counter after every source line in the first pass
+    ends: Vec<u32>,
+    lines: usize,
+    // Constants waiting on a forward reference with the PC they were set at
+    pending: Vec<(String, String, u32)>,
+    memory: Vec<u8>,
+    start: u32,
+    end: u32,
+    instructions: Vec<(u16, Instruction)>,
+    // Source files being read, the innermost include last
+    files: Vec<PathBuf>,
+    macros: HashMap<String, Macro>,
+    // Macro calls being expanded, the innermost last, and how many
+    // expansions this pass has made so far
+    expansions: Vec<Expansion>,
+    expanded: usize,
+    // Open .if blocks, the innermost last, and how many of them the file or
+    // macro being assembled found open when it started
+    conds: Vec<Cond>,
+    conds_base: usize,
+    // Every condition as decided in the first pass, the second pass keeps
+    // them so a label defined later can't change which lines get assembled
+    conditions: Vec<Result<bool, String>>,
+    checked: usize,
+    // Symbols given from the command line, set before every pass
+    defines: Vec<(String, i64)>,
+    // File and line being assembled
+    location: (String, usize),
+    errors: Vec<AsmError>,
+}
+
+impl Assembler {
+    fn new(variant: Variant, origin: u16) -> Assembler {
+        Assembler {
+            variant,
+            origin,
+            pc: origin as u32,
+            widths: Widths::default(),
+            final_pass: false,
+            symbols: HashMap::new(),
+            defined: HashSet::new(),
+            modes: vec![],
+            count: 0,
+            ends: vec![],
+            lines: 0,
+            pending: vec![],
+            memory: vec![0; MEMORY_SIZE],
+            start: u32::MAX,
+            end: 0,
+            instructions: vec![],
+            files: vec![],
+            macros: HashMap::new(),
+            expansions: vec![],
+            expanded: 0,
+            conds: vec![],
+            conds_base: 0,
+            conditions: vec![],
+            checked: 0,
+            defines: vec![],
+            location: (String::new(), 0),
+            errors: vec![],
+        }
+    }
+
+    fn run(mut self, source: &str) -> Result<Program, Vec<AsmError>> {
+        self.pass(source);
+        self.resolve_pending();
+        self.final_pass = true;
+        self.pass(source);
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
+        // Gaps left by .org and .res come out as zeros
+        let (origin, image) = if self.start < self.end {
+            (
+                self.start as u16,
+                self.memory[self.start as usize..self.end as usize].to_vec(),
+            )
+        } else {
+            (self.origin, vec![])
+        };
+        Ok(Program {
+            origin,
+            image,
+            symbols: self.symbols,
+            instructions: self.instructions,
+        })
+    }
+
+    fn pass(&mut self, source: &str) {
+        self.pc = self.origin as u32;
+        self.widths = Widths::default();
+        self.defined.clear();
+        self.count = 0;
+        self.lines = 0;
+        self.macros.clear();
+        self.expanded = 0;
+        self.conds.clear();
+        self.checked = 0;
+        for (name, val) in self.defines.clone() {
+            self.symbols.insert(name.clone(), val);
+            self.defined.insert(name);
+        }
+        self.source(source);
+    }
+
+    fn source(&mut self, source: &str) {
+        let file = match self.files.last() {
+            Some(filepath) => filepath.display().to_string(),
+            None => String::new(),
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        self.block(&file, 1, &lines);
+    }
+
+    // Assembles lines numbered from first on, taking out macro definitions
+    fn block(&mut self, file: &str, first: usize, lines: &[&str]) {
+        let outer_base = std::mem::replace(&mut self.conds_base, self.conds.len());
+        let mut idx = 0;
+        while idx < lines.len() {
+            let number = first + idx;
+            self.location = (file.to_string(), number);
+            match directive_of(lines[idx]) {
+                Some((directive, args)) if directive == ".macro" && self.active() => {
+                    let end = macro_end(&lines[idx + 1..]);
+                    let body = &lines[idx + 1..idx + 1 + end.unwrap_or(lines.len() - idx - 1)];
+                    let defined = match end {
+                        Some(_) => self.define_macro(args, body, file, number),
+                        None => Err("Missing .endmacro".to_string()),
+                    };
+                    if let Err(msg) = defined {
+                        self.error(file, number, msg);
+                    }
+                    idx += body.len() + 2;
+                }
+                _ => {
+                    self.statement(file, number, lines[idx]);
+                    idx += 1;
+                }
+            }
+        }
+
+        // A block can't run past the end of the file or macro it opened in
+        for cond in self.conds.split_off(self.conds_base) {
+            self.error(&cond.file, cond.line, "Missing .endif".to_string());
+        }
+        self.conds_base = outer_base;
+    }
+
+    fn active(&self) -> bool {
+        self.conds.last().is_none_or(|cond| cond.active)
+    }
+
+    fn statement(&mut self, file: &str, number: usize, line: &str) {
+        let statement = self.lines;
+        self.lines += 1;
+        if !self.final_pass {
+            self.ends.push(0);
+        }
+        let result = self.line(line);
+        if !self.final_pass {
+            self.ends[statement] = self.pc;
+        }
+        if let Err(msg) = result {
+            if self.final_pass {
+                // A broken line keeps its size so the labels after it stay put
+                self.pc = self.ends[statement];
+            }
+            self.error(file, number, msg);
+        }
+    }
+
+    fn error(&mut self, file: &str, line: usize, msg: String) {
+        if self.final_pass {
+            self.errors.push(AsmError {
+                file: file.to_string(),
+                line,
+                msg,
+                expansions: self.expansions.iter().rev().cloned().collect(),
+            });
+        }
+    }
+
+    fn define_macro(
+        &mut self,
+        header: &str,
+        body: &[&str],
+        file: &str,
+        line: usize,
+    ) -> Result<(), String> {
+        let (name, params) = header
+            .split_once(char::is_whitespace)
+            .unwrap_or((header, ""));
+        if !expr::is_identifier(name) {
+            return Err(format!("Invalid macro name '{}'", name));
+        }
+        let params: Vec<String> = match params.trim() {
+            "" => vec![],
+            params => split_args(params).into_iter().map(String::from).collect(),
+        };
+        if let Some(param) = params.iter().find(|param| !expr::is_identifier(param)) {
+            return Err(format!("Invalid macro parameter '{}'", param));
+        }
+        if self.macros.contains_key(name) || name.parse::<Mnemonic>().is_ok() {
+            return Err(format!("'{}' is already defined", name));
+        }
+
+        let body = body.iter().map(|line| line.to_string()).collect();
+        self.macros.insert(
+            name.to_string(),
+            Macro {
+                params,
+                body,
+                file: file.to_string(),
+                line,
+            },
+        );
+        Ok(())
+    }
+
+    fn expand(&mut self, name: &str, args: &str) -> Result<(), String> {
+        let Some(mac) = self.macros.get(name).cloned() else {
+            return Err(format!("Unknown macro '{}'", name));
+        };
+        let args = match args.trim() {
+            "" => vec![],
+            args => split_args(args),
+        };
+        if args.len() != mac.params.len() {
+            return Err(format!(
+                "Macro '{}' takes {} arguments, got {} (defined at {})",
+                name,
+                mac.params.len(),
+                args.len(),
+                location(&mac.file, mac.line)
+            ));
+        }
+        if self.expansions.len() >= MAX_EXPANSION_DEPTH {
+            return Err(format!(
+                "Macro '{}' nests more than {} deep, does it call itself?",
+                name, MAX_EXPANSION_DEPTH
+            ));
+        }
+
+        self.expanded += 1;
+        let body: Vec<String> = mac
+            .body
+            .iter()
+            .map(|line| substitute(line, &mac.params, &args, self.expanded))
+            .collect();
+        let body: Vec<&str> = body.iter().map(String::as_str).collect();
+        let (file, line) = self.location.clone();
+        self.expansions.push(Expansion {
+            name: name.to_string(),
+            file,
+            line,
+        });
+        self.block(&mac.file, mac.line + 1, &body);
+        self.expansions.pop();
+        Ok(())
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        if let Some((directive, args)) = directive_of(line) {
+            if let ".if" | ".ifdef" | ".ifndef" | ".elseif" | ".else" | ".endif" = &directive[..] {
+                return self.conditional(&directive, args);
+            }
+        }
+        if !self.active() {
+            return Ok(());
+        }
+
+        let line = strip_comment(line);
+        match split_assignment(line) {
+            Some(("*", expr)) => return self.directive(".org", expr),
+            Some((name, expr)) => return self.assign(name, expr),
+            None => (),
+        }
+
+        let (label, rest) = split_label(line, &|word| {
+            word.starts_with('.')
+                || word.parse::<Mnemonic>().is_ok()
+                || self.macros.contains_key(word)
+        });
+        if let Some(label) = label {
+            self.define(label, self.pc as i64)?;
+        }
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let (word, operand) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+        if word.starts_with('.') {
+            return self.directive(&word.to_ascii_lowercase(), operand.trim());
+        }
+        if self.macros.contains_key(word) {
+            return self.expand(word, operand);
+        }
+        let mnemonic = word.parse::<Mnemonic>()?;
+        self.instruction(mnemonic, operand)
+    }
+
+    fn directive(&mut self, name: &str, args: &str) -> Result<(), String> {
+        match name {
+            ".org" => {
+                let origin = self.known(args, name)?;
+                if !(0..=0xffff).contains(&origin) {
+                    return Err(format!("Origin {} out of range", origin));
+                }
+                self.pc = origin as u32;
+                Ok(())
+            }
+            ".byte" | ".text" | ".textz" => {
+                let mut bytes = vec![];
+                for arg in split_args(args) {
+                    if arg.starts_with('"') {
+                        bytes.extend(parse_string(arg)?);
+                    } else if name == ".byte" {
+                        bytes.push(to_byte(self.data_value(arg)?)?);
+                    } else {
+                        return Err(format!("{} takes strings, not '{}'", name, arg));
+                    }
+                }
+                if name == ".textz" {
+                    bytes.push(0x00);
+                }
+                self.data(&bytes)
+            }
+            ".word" => {
+                let mut bytes = vec![];
+                for arg in split_args(args) {
+                    bytes.extend(to_word(self.data_value(arg)?)?);
+                }
+                self.data(&bytes)
+            }
+            ".fill" | ".align" => {
+                let args = split_args(args);
+                let amount = self.known(args[0], name)?;
+                let fill = match args.get(1) {
+                    Some(arg) => to_byte(self.data_value(arg)?)?,
+                    None => 0x00,
+                };
+                let count = if name == ".fill" {
+                    amount
+                } else if amount > 0 {
+                    (amount - self.pc as i64 % amount) % amount
+                } else {
+                    return Err(format!("Invalid alignment {}", amount));
+                };
+                if !(0..=MEMORY_SIZE as i64).contains(&count) {
+                    return Err(format!("Invalid byte count {}", count));
+                }
+                self.data(&vec![fill; count as usize])
+            }
+            ".res" => {
+                let count = self.known(args, name)?;
+                if !(0..=MEMORY_SIZE as i64).contains(&count) {
+                    return Err(format!("Invalid byte count {}", count));
+                }
+                self.pc += count as u32;
+                Ok(())
+            }
+            ".incbin" => {
+                let args = split_args(args);
+                let filepath = self.resolve(&parse_string(args[0])?)?;
+                let bytes = fs::read(&filepath)
+                    .map_err(|err| format!("Can't read {}: {}", filepath.display(), err))?;
+                let offset = match args.get(1) {
+                    Some(arg) => self.known(arg, name)?,
+                    None => 0,
+                };
+                let length = match args.get(2) {
+                    Some(arg) => self.known(arg, name)?,
+                    None => bytes.len() as i64 - offset,
+                };
+                match bytes.get(offset as usize..(offset + length) as usize) {
+                    Some(bytes) if offset >= 0 && length >= 0 => self.data(bytes),
+                    _ => Err(format!(
+                        "{} holds no {} bytes at offset {}",
+                        filepath.display(),
+                        length,
+                        offset
+                    )),
+                }
+            }
+            ".include" => {
+                let filepath = self.resolve(&parse_string(args)?)?;
+                let source = fs::read_to_string(&filepath)
+                    .map_err(|err| format!("Can't read {}: {}", filepath.display(), err))?;
+                self.include(filepath, &source)
+            }
+            ".macro" => Err("A .macro line can't have a label".to_string()),
+            ".endmacro" => Err(".endmacro without .macro".to_string()),
+            // Checked once every label is known, so they can look ahead
+            ".assert" => {
+                let args = split_args(args);
+                let val = self.value(args[0])?;
+                let msg = match args.get(1) {
+                    Some(arg) => message(arg)?,
+                    None => format!("Assertion failed: {}", args[0]),
+                };
+                match val {
+                    Some(0) if self.final_pass => Err(msg),
+                    _ => Ok(()),
+                }
+            }
+            ".error" => match args {
+                "" => Err(".error reached".to_string()),
+                _ => Err(message(args)?),
+            },
+            ".if" | ".ifdef" | ".ifndef" | ".elseif" | ".else" | ".endif" => {
+                Err(format!("A {} line can't have a label", name))
+            }
+            _ => Err(format!("Unknown directive '{}'", name)),
+        }
+    }
+
+    fn conditional(&mut self, name: &str, args: &str) -> Result<(), String> {
+        if name.starts_with(".if") {
+            let outer = self.active();
+            let (file, line) = self.location.clone();
+            let taken = if outer {
+                self.condition(name, args)
+            } else {
+                Ok(false)
+            };
+            self.conds.push(Cond {
+                active: *taken.as_ref().unwrap_or(&false),
+                taken: *taken.as_ref().unwrap_or(&true),
+                outer,
+                seen_else: false,
+                file,
+                line,
+            });
+            return taken.map(|_| ());
+        }
+
+        if self.conds.len() <= self.conds_base {
+            return Err(format!("{} without .if", name));
+        }
+        let mut cond = self.conds.pop().unwrap();
+        let result = match name {
+            ".endif" => return Ok(()),
+            _ if cond.seen_else => Err(format!("{} after .else", name)),
+            ".elseif" if cond.outer && !cond.taken => {
+                let taken = self.condition(name, args);
+                cond.active = *taken.as_ref().unwrap_or(&false);
+                cond.taken = *taken.as_ref().unwrap_or(&true);
+                taken.map(|_| ())
+            }
+            ".elseif" => {
+                cond.active = false;
+                Ok(())
+            }
+            _ => {
+                cond.active = cond.outer && !cond.taken;
+                cond.taken = true;
+                cond.seen_else = true;
+                Ok(())
+            }
+        };
+        self.conds.push(cond);
+        result
+    }
+
+    fn condition(&mut self, name: &str, args: &str) -> Result<bool, String> {
+        let idx = self.checked;
+        self.checked += 1;
+        if self.final_pass {
+            return self.conditions[idx].clone();
+        }
+        let taken = match name {
+            ".ifdef" | ".ifndef" if !expr::is_identifier(args) => {
+                Err(format!("{} takes a symbol name, not '{}'", name, args))
+            }
+            ".ifdef" => Ok(self.defined.contains(args)),
+            ".ifndef" => Ok(!self.defined.contains(args)),
+            _ => self.known(args, name).map(|val| val != 0),
+        };
+        self.conditions.push(taken.clone());
+        taken
+    }
+
+    // Include paths are relative to the file holding the directive
+    fn resolve(&self, name: &[u8]) -> Result<PathBuf, String> {
+        let name = String::from_utf8(name.to_vec()).map_err(|err| err.to_string())?;
+        Ok(match self.files.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        })
+    }
+
+    fn include(&mut self, filepath: PathBuf, source: &str) -> Result<(), String> {
+        let canonical = fs::canonicalize(&filepath).ok();
+        let cycle = self
+            .files
+            .iter()
+            .position(|file| fs::canonicalize(file).ok() == canonical);
+        if let Some(idx) = cycle {
+            let chain: Vec<String> = self.files[idx..]
+                .iter()
+                .chain([&filepath])
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(format!("Include cycle {}", chain.join(" -> ")));
+        }
+
+        self.files.push(filepath);
+        self.source(source);
+        self.files.pop();
+        Ok(())
+    }
+
+    // A constant may depend on later labels, it is only entered once known
+    fn assign(&mut self, name: &str, text: &str) -> Result<(), String> {
+        match self.value(text)? {
+            Some(val) => self.define(name, val),
+            None if self.defined.insert(name.to_string()) => {
+                self.pending
+                    .push((name.to_string(), text.to_string(), self.pc));
+                Ok(())
+            }
+            None => Err(format!("'{}' is already defined", name)),
+        }
+    }
+
+    // Every label is known after the first pass, which settles the constants
+    // that referred to them, in whatever order they depend on each other
+    fn resolve_pending(&mut self) {
+        let pc = self.pc;
+        while !self.pending.is_empty() {
+            let before = self.pending.len();
+            for (name, text, at) in std::mem::take(&mut self.pending) {
+                self.pc = at;
+                match self.value(&text) {
+                    Ok(Some(val)) => {
+                        self.symbols.insert(name, val);
+                    }
+                    _ => self.pending.push((name, text, at)),
+                }
+            }
+            if self.pending.len() == before {
+                break;
+            }
+        }
+        self.pc = pc;
+    }
+
+    fn define(&mut self, name: &str, val: i64) -> Result<(), String> {
+        if !expr::is_identifier(name) {
+            return Err(format!("Invalid label '{}'", name));
+        }
+        if !self.defined.insert(name.to_string()) {
+            return Err(format!("'{}' is already defined", name));
+        }
+        if let Some(old) = self.symbols.insert(name.to_string(), val) {
+            if self.final_pass && old != val {
+                return Err(format!("'{}' moved between passes", name));
+            }
+        }
+        Ok(())
+    }
+
+    // Value of an expression, None while a forward reference is unknown
+    fn value(&self, text: &str) -> Result<Option<i64>, String> {
+        expr::eval(text, self.pc as i64, &|name| match self.symbols.get(name) {
+            Some(val) => Ok(Some(*val)),
+            None if self.final_pass => Err(format!("Undefined symbol '{}'", name)),
+            None => Ok(None),
+        })
+    }
+
+    // Layout directives can't wait for the second pass to learn their value
+    fn known(&self, text: &str, directive: &str) -> Result<i64, String> {
+        self.value(text)?
+            .ok_or(format!("{} needs a value defined before it", directive))
+    }
+
+    // Data only takes up space in the first pass, so its value can come later
+    fn data_value(&self, text: &str) -> Result<i64, String> {
+        Ok(self.value(text)?.unwrap_or(0))
+    }
+
+    fn data(&mut self, bytes: &[u8]) -> Result<(), String> {
+        if self.final_pass {
+            for byte in bytes {
+                self.emit(*byte)?;
+            }
+        } else {
+            self.pc += bytes.len() as u32;
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, mnemonic: Mnemonic, text: &str) -> Result<(), String> {
+        let idx = self.count;
+        self.count += 1;
+        let operand = Operand::parse(text);
+        if !self.final_pass {
+            let sized = self.size(mnemonic, operand);
+            self.modes
+                .push(sized.as_ref().ok().map(|(addr_mod, _)| *addr_mod));
+            self.pc += sized?.1 as u32;
+            return Ok(());
+        }
+
+        match self.modes.get(idx).copied().flatten() {
+            Some(addr_mod) => self.encode(mnemonic, operand, addr_mod),
+            // Whatever failed in the first pass fails the same way again
+            None => self
+                .size(mnemonic, operand)
+                .and(Err(format!("Can't size {}", mnemonic))),
+        }
+    }
+
+    fn values(&self, operand: Operand) -> Result<(Option<i64>, Option<i64>), String> {
+        let value = |expr: Option<&str>| match expr {
+            Some(expr) => self.value(expr),
+            None => Ok(None),
+        };
+        let (first, second) = operand.exprs();
+        Ok((value(first)?, value(second)?))
+    }
+
+    // Picks the addressing mode in the first pass and returns it with the
+    // instruction length
+    fn size(&mut self, mnemonic: Mnemonic, operand: Operand) -> Result<(AddrMod, u8), String> {
+        let (first, _) = self.values(operand)?;
+        let (addr_mod, code) = operand
+            .modes(first)
+            .into_iter()
+            .find_map(|addr_mod| {
+                optable::encode(self.variant, mnemonic, addr_mod).map(|code| (addr_mod, code))
+            })
+            .ok_or(format!("Invalid addressing mode for {}", mnemonic))?;
+        let (_, bytes) = optable::lookup(self.variant, code).sized(self.wide(mnemonic));
+        self.follow_widths(mnemonic, first);
+        Ok((addr_mod, bytes))
+    }
+
+    fn encode(
+        &mut self,
+        mnemonic: Mnemonic,
+        operand: Operand,
+        addr_mod: AddrMod,
+    ) -> Result<(), String> {
+        let (first, second) = self.values(operand)?;
+        let code = optable::encode(self.variant, mnemonic, addr_mod)
+            .ok_or(format!("Invalid addressing mode for {}", mnemonic))?;
+        let opcode = optable::lookup(self.variant, code);
+        let (addr_mod, _) = opcode.sized(self.wide(mnemonic));
+        self.follow_widths(mnemonic, first);
+
+        let pc = self.pc;
+        let operands = self.operands(addr_mod, first.unwrap_or(0), second.unwrap_or(0))?;
+        let instruction = Instruction::from_opcode(opcode, operands, addr_mod);
+        self.emit(code)?;
+        self.data(&instruction.operands)?;
+        self.instructions.push((pc as u16, instruction));
+        Ok(())
+    }
+
+    fn wide(&self, mnemonic: Mnemonic) -> bool {
+        self.variant.is_65816() && self.widths.wide(mnemonic)
+    }
+
+    // Follows REP and SEP like the disassembler does
+    fn follow_widths(&mut self, mnemonic: Mnemonic, bits: Option<i64>) {
+        match (mnemonic, bits) {
+            (Mnemonic::Rep, Some(bits)) => {
+                self.widths.wide_a |= bits & 0x20 != 0;
+                self.widths.wide_index |= bits & 0x10 != 0;
+            }
+            (Mnemonic::Sep, Some(bits)) => {
+                self.widths.wide_a &= bits & 0x20 == 0;
+                self.widths.wide_index &= bits & 0x10 == 0;
+            }
+            _ => (),
+        }
+    }
+
+    fn operands(&self, addr_mod: AddrMod, first: i64, second: i64) -> Result<Vec<u8>, String> {
+        let range = |val: i64, min: i64, max: i64| {
+            if (min..=max).contains(&val) {
+                Ok(val)
+            } else {
+                Err(format!("Value {} out of range", val))
+            }
+        };
+        let zero_page = |val| range(val, 0, 0xff).map(|val| vec![val as u8]);
+        let branch = |target: i64, len: i64| {
+            let offset = target - (self.pc as i64 + len);
+            if (-0x80..=0x7f).contains(&offset) {
+                Ok(offset as u8)
+            } else {
+                Err(format!(
+                    "Branch target {} bytes away is out of range",
+                    offset
+                ))
+            }
+        };
+
+        match addr_mod {
+            AddrMod::None | AddrMod::Implied | AddrMod::Accumulator => Ok(vec![]),
+            AddrMod::Immediate => Ok(vec![to_byte(first)?]),
+            AddrMod::ImmediateWord => Ok(to_word(first)?.to_vec()),
+            AddrMod::ZeroPage
+            | AddrMod::ZeroPageX
+            | AddrMod::ZeroPageY
+            | AddrMod::IndirectX
+            | AddrMod::IndirectY
+            | AddrMod::ZeroPageIndirect
+            | AddrMod::DirectIndirectLong
+            | AddrMod::DirectIndirectLongY
+            | AddrMod::StackRelative
+            | AddrMod::StackRelativeIndirectY => zero_page(first),
+            AddrMod::Absolute
+            | AddrMod::AbsoluteX
+            | AddrMod::AbsoluteY
+            | AddrMod::Indirect
+            | AddrMod::AbsoluteIndirectX
+            | AddrMod::AbsoluteIndirectLong => {
+                range(first, 0, 0xffff).map(|val| (val as u16).to_le_bytes().to_vec())
+            }
+            AddrMod::AbsoluteLong | AddrMod::AbsoluteLongX => {
+                range(first, 0, 0xffffff).map(|val| val.to_le_bytes()[..3].to_vec())
+            }
+            AddrMod::Relative => Ok(vec![branch(first, 2)?]),
+            AddrMod::RelativeLong => {
+                let offset = first - (self.pc as i64 + 3);
+                Ok((offset as u16).to_le_bytes().to_vec())
+            }
+            AddrMod::ZeroPageRelative => Ok(vec![zero_page(first)?[0], branch(second, 3)?]),
+            // Written source bank first, encoded destination bank first
+            AddrMod::BlockMove => Ok(vec![zero_page(second)?[0], zero_page(first)?[0]]),
+        }
+    }
+
+    fn emit(&mut self, byte: u8) -> Result<(), String> {
+        if self.pc as usize >= MEMORY_SIZE {
+            return Err("Program runs past $ffff".to_string());
+        }
+        self.memory[self.pc as usize] = byte;
+        self.start = self.start.min(self.pc);
+        self.pc += 1;
+        self.end = self.end.max(self.pc);
+        Ok(())
+    }
+}
+
+// NAME=value with a constant expression for the value, NAME alone is 1
+pub fn parse_define(text: &str) -> Result<(String, i64), String> {
+    let (name, val) = text.split_once('=').unwrap_or((text, "1"));
+    let name = name.trim();
+    if !expr::is_identifier(name) {
+        return Err(format!("Invalid define name '{}'", name));
+    }
+    let val = expr::eval(val, 0, &|name| Err(format!("Undefined symbol '{}'", name)))?
+        .ok_or(format!("Invalid define value '{}'", val))?;
+    Ok((name.to_string(), val))
+}
+
+// Assembles source text for the given variant, listing every error found.
+// Without a file of its own, its includes are relative to the working
+// directory. Defines are symbols set ahead of the source, like -D DEBUG=1.
+pub fn assemble(
+    source: &str,
+    origin: u16,
+    variant: Variant,
+    defines: &[(String, i64)],
+) -> Result<Program, Vec<AsmError>> {
+    let mut asm = Assembler::new(variant, origin);
+    asm.defines = defines.to_vec();
+    asm.run(source)
+}
+
+pub fn assemble_file(
+    filepath: &str,
+    origin: u16,
+    variant: Variant,
+    defines: &[(String, i64)],
+) -> Result<Program, Vec<AsmError>> {
+    let source = fs::read_to_string(filepath).map_err(|err| {
+        vec![AsmError {
+            file: filepath.to_string(),
+            line: 0,
+            msg: format!("Can't read it: {}", err),
+            expansions: vec![],
+        }]
+    })?;
+    let mut asm = Assembler::new(variant, origin);
+    asm.defines = defines.to_vec();
+    asm.files.push(PathBuf::from(filepath));
+    asm.run(&source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn assembled(source: &str, origin: u16) -> Vec<u8> {
+        match assemble(source, origin, Variant::Nmos6502, &[]) {
+            Ok(program) => program.image,
+            Err(errors) => panic!("{}", errors[0]),
+        }
+    }
+
+    fn bytes(source: &str) -> Vec<u8> {
+        assembled(source, 0x0600)
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        match assemble(source, 0x0600, Variant::Nmos6502, &[]) {
+            Ok(_) => vec![],
+            Err(errors) => errors.iter().map(|err| err.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn forward_references_stay_absolute() {
+        // Everything sits in the zero page, but fwd isn't known when its
+        // LDA is sized
+        let source = "
+start:  nop
+        lda start
+        lda fwd
+fwd:    rts
+";
+        assert_eq!(
+            assembled(source, 0x0000),
+            [0xea, 0xa5, 0x00, 0xad, 0x06, 0x00, 0x60]
+        );
+    }
+
+    #[test]
+    fn zero_page_indexing_falls_back_to_absolute() {
+        // There is no zero page,Y form of LDA
+        assert_eq!(
+            bytes("lda $10,x\nlda $10,y\nldx $10,y"),
+            [0xb5, 0x10, 0xb9, 0x10, 0x00, 0xb6, 0x10]
+        );
+    }
+
+    #[test]
+    fn branches_are_relative() {
+        let source = "
+loop:   dex
+        bne loop
+        beq done
+        nop
+done:   rts
+";
+        assert_eq!(bytes(source), [0xca, 0xd0, 0xfd, 0xf0, 0x01, 0xea, 0x60]);
+    }
+
+    #[test]
+    fn branch_out_of_range() {
+        let source = format!(
+            "        bne far\n{}far:    rts",
+            "        nop\n".repeat(128)
+        );
+        assert_eq!(
+            errors(&source),
+            ["line 1: Branch target 128 bytes away is out of range"]
+        );
+    }
+
+    #[test]
+    fn words_are_little_endian() {
+        assert_eq!(bytes(".word $1234, end\nend:"), [0x34, 0x12, 0x04, 0x06]);
+    }
+
+    #[test]
+    fn align_and_res_leave_gaps() {
+        assert_eq!(
+            bytes(".byte 1\n.align 4, $ea\n.byte 2\n.res 2\n.byte 3"),
+            [0x01, 0xea, 0xea, 0xea, 0x02, 0x00, 0x00, 0x03]
+        );
+    }
+
+    #[test]
+    fn include_cycles_are_reported() {
+        let dir = env::temp_dir().join(format!("emu6502-include-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.s"), ".include \"b.s\"\n").unwrap();
+        fs::write(dir.join("b.s"), "nop\n.include \"a.s\"\n").unwrap();
+        let a = dir.join("a.s").display().to_string();
+        let b = dir.join("b.s").display().to_string();
+        let result = assemble_file(&a, 0x0600, Variant::Nmos6502, &[]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let errors = result.unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].msg,
+            format!("Include cycle {} -> {} -> {}", a, b, a)
+        );
+    }
+
+    #[test]
+    fn macro_local_labels_are_renamed_per_expansion() {
+        let source = "
+        .macro wait count
+        ldx #count
+@loop:  dex
+        bne @loop
+        .endmacro
+        wait 2
+        wait 3
+";
+        let program = assemble(source, 0x0600, Variant::Nmos6502, &[]).unwrap();
+        assert_eq!(
+            program.image,
+            [0xa2, 0x02, 0xca, 0xd0, 0xfd, 0xa2, 0x03, 0xca, 0xd0, 0xfd]
+        );
+        assert_eq!(program.symbols.get("@loop@1"), Some(&0x0602));
+        assert_eq!(program.symbols.get("@loop@2"), Some(&0x0607));
+    }
+
+    #[test]
+    fn conditionals_pick_one_branch() {
+        let source = "
+.if LEVEL == 1
+        lda #1
+.elseif LEVEL == 2
+        lda #2
+.else
+        lda #3
+.endif
+";
+        for (level, val) in [(1, 1), (2, 2), (5, 3)] {
+            let defines = [("LEVEL".to_string(), level)];
+            let program = assemble(source, 0x0600, Variant::Nmos6502, &defines).unwrap();
+            assert_eq!(program.image, [0xa9, val]);
+        }
+        assert_eq!(
+            bytes(".ifdef NONE\n.error \"skipped\"\n.endif\n.ifndef NONE\nnop\n.endif"),
+            [0xea]
+        );
+        assert_eq!(errors(".if 1\nnop"), ["line 1: Missing .endif"]);
+        assert_eq!(errors(".endif"), ["line 1: .endif without .if"]);
+    }
+
+    #[test]
+    fn assertions_see_later_labels() {
+        let source = "
+        .org $06fe
+table:  .byte 1, 2, 3
+end:
+        .assert >table == >(end - 1), \"table crosses a page\"
+        .assert end - table == 3
+";
+        assert_eq!(errors(source), ["line 5: table crosses a page"]);
+        assert_eq!(
+            errors(".assert size < 2\nsize = 4"),
+            ["line 1: Assertion failed: size < 2"]
+        );
+        assert_eq!(errors(".error \"no\""), ["line 1: no"]);
+    }
+
+    #[test]
+    fn full_image_disassembles_to_ffff() {
+        let mut image = vec![0xea; 0x10000];
+        image[0xffff] = 0x60;
+        let listing = disassemble_from(
+            &|addr| image[addr as usize],
+            0x0000,
+            image.len() as u32,
+            Variant::Nmos6502,
+            Widths::default(),
+            false,
+        );
+        assert_eq!(listing.lines().count(), 0x10000);
+        assert_eq!(listing.lines().last(), Some("RTS"));
+    }
 }
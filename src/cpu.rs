@@ -1,248 +1,1274 @@
-use std::ops::Add;
-
-// use crate::addrmod::AddrMod;
 use crate::addrmod::AddrMod;
 use crate::flags::Flags;
-use crate::memory::Memory;
-use crate::{assembler, check_bit_one, get_bit};
+use crate::limits::RunLimits;
+use crate::memory::{Bus, Device, Memory};
+use crate::mnemonic::Mnemonic;
+use crate::optable::Opcode;
+use crate::outcome::{Outcome, StopReason};
+use crate::port::ProcessorPort;
+use crate::variant::Variant;
+use crate::{assembler, check_bit_one, cpu816, cycles, optable};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+pub const STACK_BASE: u16 = 0x0100;
+pub const SP_INIT: u8 = 0xfd;
+
+pub const NMI_VECTOR: u16 = 0xfffa;
+pub const RESET_VECTOR: u16 = 0xfffc;
+pub const IRQ_VECTOR: u16 = 0xfffe;
 
 #[derive(Debug)]
-pub(crate) struct CPU {
+#[allow(clippy::upper_case_acronyms)]
+pub struct CPU<B: Bus = Memory> {
     pub a: u8,
     pub x: u8,
     pub y: u8,
     pub pc: u16,
-    pub sp: u16,
+    pub sp: u8,
+    // 65C816 only: high bytes of C, X, Y and S, direct page, data and
+    // program bank, and the emulation mode bit
+    pub ah: u8,
+    pub xh: u8,
+    pub yh: u8,
+    pub sph: u8,
+    pub dp: u16,
+    pub dbr: u8,
+    pub pbr: u8,
+    pub emulation: bool,
     pub flags: Flags,
-    pub memory: Memory,
-    pub cyc: u32,
-    pub rom: Vec<u8>,
+    pub variant: Variant,
+    pub memory: B,
+    pub port: Option<ProcessorPort>,
+    pub cyc: u64,
+    pub origin: u16,
+    pub size: usize,
+    pub irq: bool,
+    pub nmi: bool,
+    pub nmi_pending: bool,
+    // I as the next interrupt poll sees it, when that lags behind the flag
+    pub poll_i: Option<bool>,
+    pub decimal_mode: bool,
+    pub brk_halts: bool,
+    pub breakpoints: Vec<u16>,
+    pub break_hit: bool,
+    pub jammed: bool,
+    pub waiting: bool,
+    // A fault seen before a dummy read, which would otherwise clear it
+    pub fault: Option<u16>,
+}
+
+impl CPU<Memory> {
+    pub fn new(rom: &[u8], origin: u16) -> CPU<Memory> {
+        CPU::with_variant(Variant::Nmos6502, rom, origin)
+    }
+
+    pub fn with_variant(variant: Variant, rom: &[u8], origin: u16) -> CPU<Memory> {
+        let mut memory = if variant.is_65816() {
+            Memory::long()
+        } else {
+            Memory::new()
+        };
+        // Images that don't carry their own vectors start at the load origin.
+        // The image is loaded over this, so one that does keeps its own.
+        let vector = RESET_VECTOR & variant.address_mask();
+        memory.poke(vector, origin as u8);
+        memory.poke(vector + 1, (origin >> 8) as u8);
+        CPU::with_bus(memory, variant, rom, origin)
+    }
 }
 
-impl CPU {
-    pub fn new(rom: &Vec<u8>) -> CPU {
-        CPU {
+impl<B: Bus> CPU<B> {
+    pub fn with_bus(bus: B, variant: Variant, rom: &[u8], origin: u16) -> CPU<B> {
+        let mut cpu = CPU {
             a: 0x00,
             x: 0x00,
             y: 0x00,
-            pc: 0x00,
-            sp: 0x00,
+            pc: origin,
+            sp: SP_INIT,
+            ah: 0x00,
+            xh: 0x00,
+            yh: 0x00,
+            sph: 0x01,
+            dp: 0x0000,
+            dbr: 0x00,
+            pbr: 0x00,
+            emulation: true,
             flags: Flags::new(),
-            memory: Memory::new(),
+            variant,
+            memory: bus,
+            port: None,
             cyc: 0x00,
-            rom: rom.clone(),
+            origin,
+            size: 0,
+            irq: false,
+            nmi: false,
+            nmi_pending: false,
+            poll_i: None,
+            decimal_mode: variant.has_decimal(),
+            brk_halts: false,
+            breakpoints: vec![],
+            break_hit: false,
+            jammed: false,
+            waiting: false,
+            fault: None,
+        };
+        cpu.set_variant(variant);
+        // The bus is left as it is, a mapped ROM supplies its own vectors
+        cpu.load(rom, origin);
+        cpu.reset();
+        cpu
+    }
+
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.decimal_mode = variant.has_decimal();
+        self.port = variant.has_processor_port().then(ProcessorPort::new);
+        if variant.is_65816() {
+            self.reset816();
+        }
+    }
+
+    pub fn load(&mut self, rom: &[u8], origin: u16) {
+        self.memory.load(origin & self.variant.address_mask(), rom);
+        self.origin = origin;
+        self.size = rom.len();
+    }
+
+    // Every access the CPU makes goes through its pins, so address lines the
+    // package lacks are dropped and the 6510 port shadows $00 and $01
+    pub fn bus_read(&mut self, addr: u16) -> u8 {
+        let addr = addr & self.variant.address_mask();
+        match self.port.as_mut() {
+            Some(port) if addr <= 0x0001 => port.read(addr),
+            _ => self.memory.read(addr),
+        }
+    }
+
+    pub fn bus_write(&mut self, addr: u16, val: u8) {
+        let addr = addr & self.variant.address_mask();
+        if let Some(port) = self.port.as_mut().filter(|_| addr <= 0x0001) {
+            port.write(addr, val);
+        }
+        // The RAM underneath the port still sees the write
+        self.memory.write(addr, val);
+    }
+
+    pub fn bus_peek(&self, addr: u16) -> u8 {
+        let addr = addr & self.variant.address_mask();
+        match self.port.as_ref() {
+            Some(port) if addr <= 0x0001 => port.peek(addr),
+            _ => self.memory.peek(addr),
         }
     }
 
     pub fn reset(&mut self) {
+        // Reset runs a suppressed interrupt sequence, the three stack
+        // pushes are turned into reads so SP ends up at $FD
         self.a = 0x00;
         self.x = 0x00;
         self.y = 0x00;
-        self.pc = 0x00;
-        self.sp = 0x00;
+        self.sp = SP_INIT;
         self.flags = Flags::new();
-        self.memory = Memory::new();
-        self.cyc = 0x00;
+        self.flags.trig_i_if(true);
+        self.irq = false;
+        self.nmi = false;
+        self.nmi_pending = false;
+        self.poll_i = None;
+        if let Some(port) = self.port.as_mut() {
+            // Reset turns every port pin back into an input
+            port.ddr = 0x00;
+            port.data = 0x00;
+        }
+        self.break_hit = false;
+        self.jammed = false;
+        self.waiting = false;
+        self.cyc = cycles::RESET as u64;
+        if self.variant.is_65816() {
+            self.reset816();
+        }
+        self.pc = self.read_word(RESET_VECTOR);
     }
 
-    pub fn add(&self, a: u8, b: u8) -> u8 {
-        a + b
+    // Reads the chip makes and throws the value of. They show up on the bus,
+    // but uninitialized memory there is no fault.
+    pub fn dummy_read(&mut self, addr: u16) {
+        let fault = self.memory.take_fault();
+        self.bus_read(addr);
+        self.memory.take_fault();
+        self.fault = self.fault.or(fault);
     }
 
-    pub fn sub(&self, a: u8, b: u8) -> u8 {
-        a - b
+    pub fn read_word(&mut self, addr: u16) -> u16 {
+        let lo = self.bus_read(addr);
+        let hi = self.bus_read(addr.wrapping_add(1));
+        u16::from_le_bytes([lo, hi])
     }
 
-    pub fn mul(&self, a: u8, b: u8) -> u8 {
-        a * b
+    pub fn set_irq(&mut self, asserted: bool) {
+        // IRQ is level triggered, it stays active until the device releases it
+        self.irq = asserted;
     }
 
-    pub fn div(&self, a: u8, b: u8) -> u8 {
-        a / b
+    pub fn set_nmi(&mut self, asserted: bool) {
+        // NMI is edge triggered, only the transition to asserted is latched
+        if asserted && !self.nmi {
+            self.nmi_pending = true;
+        }
+        self.nmi = asserted;
+    }
+
+    pub fn interrupt(&mut self, vector: u16, ret: u16, brk: bool) {
+        if self.variant.is_65816() {
+            let native_vector = vector - cpu816::NATIVE_VECTOR_OFFSET;
+            return self.interrupt816(vector, native_vector, ret, brk);
+        }
+        self.push_word(ret);
+        self.push(self.flags.pushed(brk));
+        self.flags.trig_i_if(true);
+        if self.variant.is_cmos() {
+            // The 65C02 also leaves decimal mode when taking an interrupt
+            self.flags.trig_d_if(false);
+        }
+        self.pc = self.read_word(vector);
+    }
+
+    pub fn poll_interrupts(&mut self) -> Option<u16> {
+        let i = self.poll_i.take().unwrap_or(self.flags.i);
+        let vector = if self.nmi_pending {
+            self.nmi_pending = false;
+            NMI_VECTOR
+        } else if self.irq && !i {
+            IRQ_VECTOR
+        } else {
+            return None;
+        };
+        // The opcode fetch is thrown away and read again before the pushes
+        self.dummy_read(self.pc);
+        self.dummy_read(self.pc);
+        self.interrupt(vector, self.pc, false);
+        self.cyc += cycles::INTERRUPT as u64;
+        Some(vector)
+    }
+
+    // CLI, SEI and PLP change I after the interrupt poll in their last
+    // cycle, so the next poll still goes by the old value
+    pub fn poll_old_i(&mut self, old: bool) {
+        if self.flags.i != old {
+            self.poll_i = Some(old);
+        }
     }
 
-    pub fn unimpl() {
-        println!("unimpl");
+    pub fn in_program(&self, addr: u16) -> bool {
+        addr >= self.origin && ((addr - self.origin) as usize) < self.size
+    }
+
+    pub fn disassemble(&self, start: u16, end: u32) -> String {
+        let program_bank = (self.pbr as u32) << 16;
+        assembler::disassemble_from(
+            &|addr| self.bus_peek_long(program_bank | addr as u32),
+            start,
+            end,
+            self.variant,
+            self.widths(),
+            true,
+        )
     }
 
     pub fn update_pc(&mut self, pc: u16) {
         self.pc = pc;
     }
 
-    pub fn read(&mut self, addr_mod: &AddrMod, operands: &Vec<u8>) -> Option<u8> {
+    // Operand bytes following the opcode at pc, read without bus side effects
+    pub fn fetch_operands(&self, bank: u32, pc: u16, bytes: u8) -> [u8; 3] {
+        let mut operands = [0x00; 3];
+        for (offset, operand) in operands
+            .iter_mut()
+            .enumerate()
+            .take(bytes.saturating_sub(1) as usize)
+        {
+            *operand = self.bus_peek_long(bank | pc.wrapping_add(offset as u16 + 1) as u32);
+        }
+        operands
+    }
+
+    // Fetches the opcode and its operands at PC over the bus. Single byte
+    // instructions still read the byte after them, apart from the 65C02's
+    // one cycle NOPs, and JSR leaves its high byte until the return address
+    // is pushed.
+    pub fn read_operands(&mut self, entry: &Opcode) -> [u8; 3] {
+        let mut operands = [0x00; 3];
+        self.bus_read(self.pc);
+        let count = match entry.mnemonic {
+            Mnemonic::Jsr => 1,
+            _ if entry.cycles == 1 => 0,
+            _ => entry.bytes.saturating_sub(1).max(1),
+        };
+        for offset in 0..count {
+            let addr = self.pc.wrapping_add(offset as u16 + 1);
+            if offset + 1 < entry.bytes {
+                operands[offset as usize] = self.bus_read(addr);
+            } else {
+                self.dummy_read(addr);
+            }
+        }
+        operands
+    }
+
+    pub fn zp_word(&mut self, ptr: u8) -> u16 {
+        // Pointers in the zero page wrap around to $00 instead of reaching $0100
+        let lo = self.bus_read(ptr as u16);
+        let hi = self.bus_read(ptr.wrapping_add(1) as u16);
+        u16::from_le_bytes([lo, hi])
+    }
+
+    // Returns the effective address and whether indexing crossed a page,
+    // making the same bus cycles as the chip on the way. Reads only spend
+    // the cycle fixing up the high byte when indexing crosses a page, writes
+    // always do as the access can't be taken back.
+    pub fn resolve_addr(
+        &mut self,
+        addr_mod: &AddrMod,
+        operands: &[u8],
+        write: bool,
+    ) -> Option<(u16, bool)> {
+        // Address of the last operand byte, which the 65C02 reads again
+        // where the NMOS part reads from a half computed address
+        let last = self.pc.wrapping_add(operands.len() as u16);
+        let indexed = |cpu: &mut Self, base: u16, index: u8| {
+            let addr = base.wrapping_add(index as u16);
+            let crossed = cycles::page_crossed(base, addr);
+            if crossed || write {
+                let dummy = if cpu.variant.is_cmos() && crossed {
+                    last
+                } else {
+                    (base & 0xff00) | (addr & 0x00ff)
+                };
+                cpu.dummy_read(dummy);
+            }
+            (addr, crossed)
+        };
+
+        match addr_mod {
+            AddrMod::ZeroPage => Some((operands[0] as u16, false)),
+            AddrMod::ZeroPageX | AddrMod::ZeroPageY => {
+                // The base is read while the index is added
+                self.dummy_read(operands[0] as u16);
+                let index = if *addr_mod == AddrMod::ZeroPageX {
+                    self.x
+                } else {
+                    self.y
+                };
+                Some((operands[0].wrapping_add(index) as u16, false))
+            }
+            AddrMod::Absolute => Some((u16::from_le_bytes([operands[0], operands[1]]), false)),
+            AddrMod::AbsoluteX => {
+                let (base, x) = (u16::from_le_bytes([operands[0], operands[1]]), self.x);
+                Some(indexed(self, base, x))
+            }
+            AddrMod::AbsoluteY => {
+                let (base, y) = (u16::from_le_bytes([operands[0], operands[1]]), self.y);
+                Some(indexed(self, base, y))
+            }
+            AddrMod::ZeroPageRelative => Some((operands[0] as u16, false)),
+            AddrMod::Indirect => {
+                // The NMOS part fetches the pointer's high byte without
+                // carrying into the page, so JMP ($10FF) reads $10FF and $1000
+                let ptr = u16::from_le_bytes([operands[0], operands[1]]);
+                let hi_ptr = if self.variant.is_cmos() {
+                    self.dummy_read(last);
+                    ptr.wrapping_add(1)
+                } else {
+                    (ptr & 0xff00) | (ptr.wrapping_add(1) & 0x00ff)
+                };
+                let lo = self.bus_read(ptr);
+                let hi = self.bus_read(hi_ptr);
+                Some((u16::from_le_bytes([lo, hi]), false))
+            }
+            AddrMod::IndirectX => {
+                self.dummy_read(operands[0] as u16);
+                let addr = self.zp_word(operands[0].wrapping_add(self.x));
+                Some((addr, false))
+            }
+            AddrMod::IndirectY => {
+                let (base, y) = (self.zp_word(operands[0]), self.y);
+                Some(indexed(self, base, y))
+            }
+            AddrMod::ZeroPageIndirect => Some((self.zp_word(operands[0]), false)),
+            AddrMod::AbsoluteIndirectX => {
+                self.dummy_read(last);
+                let ptr =
+                    u16::from_le_bytes([operands[0], operands[1]]).wrapping_add(self.x as u16);
+                Some((self.read_word(ptr), false))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn read(&mut self, addr_mod: &AddrMod, operands: &[u8]) -> Option<u8> {
         match addr_mod {
             AddrMod::Immediate => Some(operands[0]),
-            AddrMod::ZeroPage => Some(self.memory.read(operands[0] as u16)),
-            AddrMod::ZeroPageX => Some(self.memory.read((operands[0] + self.x) as u16)),
-            AddrMod::ZeroPageY => Some(self.memory.read((operands[0] + self.y) as u16)),
-            AddrMod::Absolute => Some(self.memory.read((operands[0] | operands[1]) as u16)),
-            AddrMod::AbsoluteX => Some(
-                self.memory
-                    .read((operands[0] | operands[1]) as u16 + self.x as u16),
-            ),
-            AddrMod::AbsoluteY => Some(
-                self.memory
-                    .read((operands[0] | operands[1]) as u16 + self.y as u16),
-            ),
-            AddrMod::Indirect => Some(
-                self.memory
-                    .read(self.memory.read(operands[0] as u16) as u16),
-            ),
-            AddrMod::IndirectX => Some(
-                self.memory
-                    .read((self.memory.read(operands[0] as u16) as u16 + self.x as u16) as u16),
-            ),
-            AddrMod::IndirectY => Some(
-                self.memory
-                    .read((self.memory.read(operands[0] as u16) as u16 + self.y as u16) as u16),
-            ),
-            _ => None,
+            AddrMod::Accumulator => Some(self.a),
+            _ => {
+                let (addr, _) = self.resolve_addr(addr_mod, operands, false)?;
+                Some(self.bus_read(addr))
+            }
         }
     }
 
-    pub fn mem_write(&mut self, addr_mod: &AddrMod, operands: &Vec<u8>, val: u8) {
+    pub fn mem_write(&mut self, addr_mod: &AddrMod, operands: &[u8], val: u8) {
         match addr_mod {
-            AddrMod::ZeroPage => self.memory.write(operands[0] as u16, val),
-            AddrMod::ZeroPageX => self.memory.write((operands[0] + self.x) as u16, val),
-            AddrMod::ZeroPageY => self.memory.write((operands[0] + self.y) as u16, val),
-            AddrMod::Absolute => self.memory.write((operands[0] | operands[1]) as u16, val),
-            AddrMod::AbsoluteX => self
-                .memory
-                .write((operands[0] | operands[1]) as u16 + self.x as u16, val),
-            AddrMod::AbsoluteY => self
-                .memory
-                .write((operands[0] | operands[1]) as u16 + self.y as u16, val),
-            AddrMod::Indirect => self
-                .memory
-                .write(self.memory.read(operands[0] as u16) as u16, val),
-            AddrMod::IndirectX => self.memory.write(
-                self.memory.read(operands[0] as u16) as u16 + self.x as u16,
-                val,
-            ),
-            AddrMod::IndirectY => self.memory.write(
-                self.memory.read(operands[0] as u16) as u16 + self.y as u16,
-                val,
-            ),
-            _ => (),
-        }
-    }
-
-    pub fn unwrap_operands(operands: &Vec<u8>) -> Option<(u8, Option<u8>)> {
-        if operands.len() == 1 {
-            return Some((operands[0], None));
-        } else if operands.len() == 2 {
-            return Some((operands[0], Some(operands[1])));
+            AddrMod::Accumulator => self.a = val,
+            _ => {
+                if let Some((addr, _)) = self.resolve_addr(addr_mod, operands, true) {
+                    self.bus_write(addr, val);
+                }
+            }
+        }
+    }
+
+    // Read-modify-write, returning the value written back. The NMOS part
+    // writes the old value again while it works out the new one, the 65C02
+    // reads it again instead. Its shifts only fix the address up when
+    // indexing crosses a page, which fix_up false asks for.
+    pub fn modify(
+        &mut self,
+        addr_mod: &AddrMod,
+        operands: &[u8],
+        fix_up: bool,
+        op: impl FnOnce(&mut Self, u8) -> u8,
+    ) -> Option<u8> {
+        if *addr_mod == AddrMod::Accumulator {
+            self.a = op(self, self.a);
+            return Some(self.a);
+        }
+        let (addr, _) = self.resolve_addr(addr_mod, operands, fix_up)?;
+        let val = self.bus_read(addr);
+        if self.variant.is_cmos() {
+            self.dummy_read(addr);
         } else {
-            return None;
+            self.bus_write(addr, val);
         }
+        let val = op(self, val);
+        self.bus_write(addr, val);
+        Some(val)
+    }
+
+    pub fn set_zn(&mut self, val: u8) {
+        self.flags.trig_z_if(val == 0);
+        self.flags.trig_n_if(check_bit_one!(val, 7));
+    }
+
+    pub fn compare(&mut self, reg: u8, val: u8) {
+        self.flags.trig_c_if(reg >= val);
+        self.set_zn(reg.wrapping_sub(val));
+    }
+
+    pub fn add_with_carry(&mut self, val: u8) {
+        let sum = self.a as u16 + val as u16 + self.flags.c as u16;
+        let result = sum as u8;
+
+        self.flags.trig_c_if(sum > 0xff);
+        self.flags
+            .trig_v_if(check_bit_one!((self.a ^ result) & (val ^ result), 7));
+        self.a = result;
+        self.set_zn(result);
     }
 
-    pub fn step(&mut self) {
-        if self.pc >= self.rom.len() as u16 {
-            println!("PC (0x{:0>2x}) out of bounds", self.pc);
-            return;
+    pub fn adc(&mut self, val: u8) {
+        if self.flags.d && self.decimal_mode {
+            self.add_decimal(val);
+            if self.variant.is_cmos() {
+                // The 65C02 spends a cycle fixing N and Z up for the result
+                self.set_zn(self.a);
+                self.cyc += 1;
+            }
+        } else {
+            self.add_with_carry(val);
         }
-        let (instr, next) = assembler::next_instruction(&self.rom, self.pc);
+    }
 
-        match instr.opcode.as_str() {
-            "ADC" => {
-                // Add with carry
-                let val = self.read(&instr.addr_mod, &instr.operands);
-                if !val.is_none() {
-                    let b7 = get_bit!(self.a, 7);
-
-                    match self.a.checked_add(val.unwrap()) {
-                        Some(v) => {
-                            self.a += v;
-                            self.flags.trig_c_if(false);
-                        }
-                        None => {
-                            self.a = 0xff;
-                            self.flags.trig_c_if(true);
-                        }
-                    };
+    pub fn sbc(&mut self, val: u8) {
+        // A - M - (1 - C) == A + !M + C, decimal mode only changes A
+        let (a, carry) = (self.a, self.flags.c as u8);
+        self.add_with_carry(!val);
+        if self.flags.d && self.decimal_mode {
+            if self.variant.is_cmos() {
+                self.a = Self::sub_decimal_cmos(a, val, carry);
+                self.set_zn(self.a);
+                self.cyc += 1;
+            } else {
+                self.a = Self::sub_decimal(a, val, carry);
+            }
+        }
+    }
+
+    pub fn add_decimal(&mut self, val: u8) {
+        // NMOS behaviour: Z comes from the binary sum, N and V from the sum
+        // before the high nibble is adjusted
+        let (a, b, c) = (self.a as u16, val as u16, self.flags.c as u16);
+        let binary = (a + b + c) as u8;
+
+        let mut lo = (a & 0x0f) + (b & 0x0f) + c;
+        if lo >= 0x0a {
+            lo = ((lo + 0x06) & 0x0f) + 0x10;
+        }
+        let mut sum = (a & 0xf0) + (b & 0xf0) + lo;
+        let signed = (a & 0xf0) as u8 as i8 as i16 + (b & 0xf0) as u8 as i8 as i16 + lo as i16;
+
+        self.flags.trig_z_if(binary == 0);
+        self.flags.trig_n_if(check_bit_one!(sum, 7));
+        self.flags.trig_v_if(!(-128..=127).contains(&signed));
+
+        if sum >= 0xa0 {
+            sum += 0x60;
+        }
+        self.flags.trig_c_if(sum >= 0x100);
+        self.a = sum as u8;
+    }
+
+    pub fn sub_decimal(a: u8, val: u8, carry: u8) -> u8 {
+        let (a, b, c) = (a as i16, val as i16, carry as i16);
 
-                    self.flags.trig_z_if(self.a == 0);
-                    self.flags.trig_n_if(check_bit_one!(self.a, 7));
-                    self.flags.trig_v_if(get_bit!(self.a, 7) != b7);
+        let mut lo = (a & 0x0f) - (b & 0x0f) + c - 1;
+        if lo < 0 {
+            lo = ((lo - 0x06) & 0x0f) - 0x10;
+        }
+        let mut diff = (a & 0xf0) - (b & 0xf0) + lo;
+        if diff < 0 {
+            diff -= 0x60;
+        }
+        diff as u8
+    }
+
+    pub fn sub_decimal_cmos(a: u8, val: u8, carry: u8) -> u8 {
+        // The 65C02 adjusts the whole binary difference instead of each nibble
+        let (a, b, c) = (a as i16, val as i16, carry as i16);
+
+        let lo = (a & 0x0f) - (b & 0x0f) + c - 1;
+        let mut diff = a - b + c - 1;
+        if diff < 0 {
+            diff -= 0x60;
+        }
+        if lo < 0 {
+            diff -= 0x06;
+        }
+        diff as u8
+    }
+
+    pub fn shift(&mut self, kind: Mnemonic, val: u8) -> u8 {
+        let carry_in = self.flags.c as u8;
+        let (result, carry_out) = match kind {
+            Mnemonic::Asl => (val << 1, check_bit_one!(val, 7)),
+            Mnemonic::Lsr => (val >> 1, check_bit_one!(val, 0)),
+            Mnemonic::Rol => (val << 1 | carry_in, check_bit_one!(val, 7)),
+            _ => (val >> 1 | carry_in << 7, check_bit_one!(val, 0)),
+        };
+
+        self.flags.trig_c_if(carry_out);
+        self.set_zn(result);
+        result
+    }
+
+    pub fn arr(&mut self, val: u8) {
+        // AND then ROR, with C and V taken from bits 6 and 5 of the result
+        let and = self.a & val;
+        let mut result = and >> 1 | (self.flags.c as u8) << 7;
+
+        if self.flags.d && self.decimal_mode {
+            // Decimal mode fixes up each nibble of the rotated value
+            let (hi, lo) = (and >> 4, and & 0x0f);
+            self.set_zn(result);
+            self.flags.trig_v_if(check_bit_one!(and ^ result, 6));
+            if lo + (lo & 1) > 5 {
+                result = (result & 0xf0) | (result.wrapping_add(6) & 0x0f);
+            }
+            self.flags.trig_c_if(hi + (hi & 1) > 5);
+            if self.flags.c {
+                result = result.wrapping_add(0x60);
+            }
+        } else {
+            self.set_zn(result);
+            self.flags.trig_c_if(check_bit_one!(result, 6));
+            self.flags
+                .trig_v_if(check_bit_one!(result, 6) != check_bit_one!(result, 5));
+        }
+        self.a = result;
+    }
+
+    pub fn store_and_high(&mut self, addr_mod: &AddrMod, operands: &[u8], val: u8, index: u8) {
+        // Stores val AND (high byte of the base address + 1). When indexing
+        // crosses a page the stored value also replaces the address high byte.
+        if let Some((addr, crossed)) = self.resolve_addr(addr_mod, operands, true) {
+            let base_hi = (addr.wrapping_sub(index as u16) >> 8) as u8;
+            let val = val & base_hi.wrapping_add(1);
+            let addr = if crossed {
+                (val as u16) << 8 | (addr & 0x00ff)
+            } else {
+                addr
+            };
+            self.bus_write(addr, val);
+        }
+    }
+
+    pub fn branch(&mut self, condition: bool, offset: u8, next: u16) -> u16 {
+        if condition {
+            // A taken branch costs one cycle, two if it lands on another page
+            // with the opcode after the branch read while the offset is added
+            // and a read from the wrong page while the high byte is fixed up
+            let target = next.wrapping_add(offset as i8 as u16);
+            let crossed = cycles::page_crossed(next, target);
+            self.cyc += 1 + crossed as u64;
+            self.dummy_read(next);
+            if crossed {
+                self.dummy_read((next & 0xff00) | (target & 0x00ff));
+            }
+            target
+        } else {
+            next
+        }
+    }
+
+    pub fn page_penalty(&mut self, addr_mod: &AddrMod, operands: &[u8]) -> u64 {
+        // Indexed reads take an extra cycle to fix up the high byte. Worked
+        // out without bus cycles, the instruction makes those itself.
+        let (base, index) = match addr_mod {
+            AddrMod::AbsoluteX => (u16::from_le_bytes([operands[0], operands[1]]), self.x),
+            AddrMod::AbsoluteY => (u16::from_le_bytes([operands[0], operands[1]]), self.y),
+            AddrMod::IndirectY => {
+                let ptr = operands[0];
+                let lo = self.bus_peek(ptr as u16);
+                let hi = self.bus_peek(ptr.wrapping_add(1) as u16);
+                (u16::from_le_bytes([lo, hi]), self.y)
+            }
+            _ => return 0,
+        };
+        cycles::page_crossed(base, base.wrapping_add(index as u16)) as u64
+    }
+
+    pub fn push(&mut self, val: u8) {
+        // The stack lives in page $01 and grows downwards, SP wraps inside it
+        self.bus_write(STACK_BASE | self.sp as u16, val);
+        self.sp = self.sp.wrapping_sub(1);
+    }
+
+    pub fn pull(&mut self) -> u8 {
+        self.sp = self.sp.wrapping_add(1);
+        self.bus_read(STACK_BASE | self.sp as u16)
+    }
+
+    pub fn push_word(&mut self, val: u16) {
+        // High byte first so the word sits little-endian in memory
+        self.push((val >> 8) as u8);
+        self.push(val as u8);
+    }
+
+    pub fn pull_word(&mut self) -> u16 {
+        let lo = self.pull();
+        let hi = self.pull();
+        u16::from_le_bytes([lo, hi])
+    }
+
+    pub fn status(&self) -> u8 {
+        u8::from(self.flags)
+    }
+
+    pub fn set_status(&mut self, status: u8) {
+        self.flags.pull(status);
+    }
+
+    pub fn step(&mut self) -> Outcome {
+        let pc = self.pc;
+        let opcode = self.bus_peek_long((self.pbr as u32) << 16 | pc as u32);
+
+        // A jammed CPU ignores interrupts, only a reset brings it back
+        if self.jammed {
+            return Outcome::Halted { pc, opcode };
+        }
+
+        // WAI resumes on any interrupt line, even an IRQ masked by I
+        if self.waiting {
+            if !self.nmi_pending && !self.irq {
+                self.cyc += 1;
+                return Outcome::Waiting { pc, opcode };
+            }
+            self.waiting = false;
+        }
+
+        if let Some(vector) = self.poll_interrupts() {
+            return Outcome::Interrupt { pc, opcode, vector };
+        }
+
+        // Stop once on a breakpoint, the following step executes it
+        if !self.break_hit && self.breakpoints.contains(&pc) {
+            self.break_hit = true;
+            return Outcome::Breakpoint { pc, opcode };
+        }
+        self.break_hit = false;
+
+        if self.variant.is_65816() {
+            return self.execute816(pc, opcode);
+        }
+
+        let entry = optable::lookup(self.variant, opcode);
+        let operands = &self.read_operands(entry)[..entry.bytes as usize - 1];
+        let addr_mod = &entry.addr_mod;
+        let mut next = pc.wrapping_add(entry.bytes as u16);
+
+        self.cyc += entry.cycles as u64;
+        let penalised = match entry.mnemonic {
+            Mnemonic::Adc
+            | Mnemonic::And
+            | Mnemonic::Cmp
+            | Mnemonic::Eor
+            | Mnemonic::Lda
+            | Mnemonic::Ldx
+            | Mnemonic::Ldy
+            | Mnemonic::Ora
+            | Mnemonic::Sbc
+            | Mnemonic::Lax
+            | Mnemonic::Las
+            | Mnemonic::Nop
+            | Mnemonic::Bit => true,
+            // The 65C02 shortened indexed shifts, they only pay on a page cross
+            Mnemonic::Asl | Mnemonic::Lsr | Mnemonic::Rol | Mnemonic::Ror => self.variant.is_cmos(),
+            _ => false,
+        };
+        if penalised {
+            self.cyc += self.page_penalty(addr_mod, operands);
+        }
+
+        match entry.mnemonic {
+            Mnemonic::Lda => {
+                // Accumulator <- Memory
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.a = val;
+                    self.set_zn(self.a);
+                }
+            }
+            Mnemonic::Ldx => {
+                // X <- Memory
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.x = val;
+                    self.set_zn(self.x);
+                }
+            }
+            Mnemonic::Ldy => {
+                // Y <- Memory
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.y = val;
+                    self.set_zn(self.y);
                 }
             }
-            "AND" => {
+            Mnemonic::Sta => {
+                // Memory <- Accumulator
+                self.mem_write(addr_mod, operands, self.a);
+            }
+            Mnemonic::Stx => {
+                // Memory <- X
+                self.mem_write(addr_mod, operands, self.x);
+            }
+            Mnemonic::Sty => {
+                // Memory <- Y
+                self.mem_write(addr_mod, operands, self.y);
+            }
+            Mnemonic::Tax => {
+                self.x = self.a;
+                self.set_zn(self.x);
+            }
+            Mnemonic::Tay => {
+                self.y = self.a;
+                self.set_zn(self.y);
+            }
+            Mnemonic::Txa => {
+                self.a = self.x;
+                self.set_zn(self.a);
+            }
+            Mnemonic::Tya => {
+                self.a = self.y;
+                self.set_zn(self.a);
+            }
+            Mnemonic::Tsx => {
+                self.x = self.sp;
+                self.set_zn(self.x);
+            }
+            Mnemonic::Txs => {
+                // No flags are affected
+                self.sp = self.x;
+            }
+            Mnemonic::Pha => {
+                self.push(self.a);
+            }
+            Mnemonic::Php => {
+                // PHP always pushes with the B bit set
+                self.push(self.flags.pushed(true));
+            }
+            Mnemonic::Pla => {
+                self.dummy_read(STACK_BASE | self.sp as u16);
+                self.a = self.pull();
+                self.set_zn(self.a);
+            }
+            Mnemonic::Plp => {
+                self.dummy_read(STACK_BASE | self.sp as u16);
+                let (status, old) = (self.pull(), self.flags.i);
+                self.set_status(status);
+                self.poll_old_i(old);
+            }
+            Mnemonic::And => {
                 // AND
-                let val = self.read(&instr.addr_mod, &instr.operands);
-                if !val.is_none() {
-                    self.a &= val.unwrap();
-                    self.flags.trig_z_if(self.a == 0);
-                    self.flags.trig_n_if(check_bit_one!(self.a, 7));
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.a &= val;
+                    self.set_zn(self.a);
                 }
             }
-            "ASL" => {
-                if instr.addr_mod == AddrMod::Accumulator {
-                    self.flags.trig_c_if(check_bit_one!(self.a, 7));
-                    self.a = self.a << 1;
-                } else {
-                    let val = self.read(&instr.addr_mod, &instr.operands);
-                    if !val.is_none() {
-                        self.flags.trig_c_if(check_bit_one!(val.unwrap(), 7));
-                        self.mem_write(&instr.addr_mod, &instr.operands, val.unwrap() << 1);
+            Mnemonic::Eor => {
+                // Exclusive OR
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.a ^= val;
+                    self.set_zn(self.a);
+                }
+            }
+            Mnemonic::Ora => {
+                // Inclusive OR
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.a |= val;
+                    self.set_zn(self.a);
+                }
+            }
+            Mnemonic::Bit => {
+                // Bit test, the immediate form only affects Z
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.flags.trig_z_if(self.a & val == 0);
+                    if *addr_mod != AddrMod::Immediate {
+                        self.flags.trig_n_if(check_bit_one!(val, 7));
+                        self.flags.trig_v_if(check_bit_one!(val, 6));
                     }
                 }
-                self.flags.trig_z_if(self.a == 0);
-                self.flags.trig_n_if(check_bit_one!(self.a, 7));
             }
-            "BCC" => {
-                // Branch if carry clear
-                if !self.flags.c {
-                    self.pc += instr.operands[0] as u16;
+            Mnemonic::Adc => {
+                // Add with carry
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.adc(val);
                 }
             }
-            "BCS" => {
-                // Branch if carry set
-                if self.flags.c {
-                    self.pc += instr.operands[0] as u16;
+            Mnemonic::Sbc => {
+                // Subtract with carry
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.sbc(val);
+                }
+            }
+            Mnemonic::Cmp => {
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.compare(self.a, val);
+                }
+            }
+            Mnemonic::Cpx => {
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.compare(self.x, val);
+                }
+            }
+            Mnemonic::Cpy => {
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.compare(self.y, val);
+                }
+            }
+            Mnemonic::Inc => {
+                if let Some(val) =
+                    self.modify(addr_mod, operands, true, |_, val| val.wrapping_add(1))
+                {
+                    self.set_zn(val);
+                }
+            }
+            Mnemonic::Inx => {
+                self.x = self.x.wrapping_add(1);
+                self.set_zn(self.x);
+            }
+            Mnemonic::Iny => {
+                self.y = self.y.wrapping_add(1);
+                self.set_zn(self.y);
+            }
+            Mnemonic::Dec => {
+                if let Some(val) =
+                    self.modify(addr_mod, operands, true, |_, val| val.wrapping_sub(1))
+                {
+                    self.set_zn(val);
+                }
+            }
+            Mnemonic::Dex => {
+                self.x = self.x.wrapping_sub(1);
+                self.set_zn(self.x);
+            }
+            Mnemonic::Dey => {
+                self.y = self.y.wrapping_sub(1);
+                self.set_zn(self.y);
+            }
+            Mnemonic::Asl | Mnemonic::Lsr | Mnemonic::Rol | Mnemonic::Ror => {
+                let fix_up = !self.variant.is_cmos();
+                self.modify(addr_mod, operands, fix_up, |cpu, val| {
+                    cpu.shift(entry.mnemonic, val)
+                });
+            }
+            Mnemonic::Jmp => {
+                if let Some((addr, _)) = self.resolve_addr(addr_mod, operands, false) {
+                    next = addr;
                 }
             }
-            "BEQ" => {
+            Mnemonic::Jsr => {
+                // Pushes the address of the last byte of JSR, RTS adds one back.
+                // That byte is only read once the push is done.
+                self.dummy_read(STACK_BASE | self.sp as u16);
+                self.push_word(next.wrapping_sub(1));
+                let hi = self.bus_read(next.wrapping_sub(1));
+                next = u16::from_le_bytes([operands[0], hi]);
+            }
+            Mnemonic::Rts => {
+                self.dummy_read(STACK_BASE | self.sp as u16);
+                let ret = self.pull_word();
+                self.dummy_read(ret);
+                next = ret.wrapping_add(1);
+            }
+            Mnemonic::Rti => {
+                // Unlike RTS the pulled address is used as is
+                self.dummy_read(STACK_BASE | self.sp as u16);
+                let status = self.pull();
+                self.set_status(status);
+                next = self.pull_word();
+            }
+            Mnemonic::Bcc => {
+                // Branch if carry clear
+                next = self.branch(!self.flags.c, operands[0], next);
+            }
+            Mnemonic::Bcs => {
+                // Branch if carry set
+                next = self.branch(self.flags.c, operands[0], next);
+            }
+            Mnemonic::Beq => {
                 // Branch if equal
-                if self.flags.z {
-                    self.pc += instr.operands[0] as u16;
+                next = self.branch(self.flags.z, operands[0], next);
+            }
+            Mnemonic::Bne => {
+                // Branch if not equal
+                next = self.branch(!self.flags.z, operands[0], next);
+            }
+            Mnemonic::Bmi => {
+                // Branch if minus
+                next = self.branch(self.flags.n, operands[0], next);
+            }
+            Mnemonic::Bpl => {
+                // Branch if positive
+                next = self.branch(!self.flags.n, operands[0], next);
+            }
+            Mnemonic::Bvc => {
+                // Branch if overflow clear
+                next = self.branch(!self.flags.v, operands[0], next);
+            }
+            Mnemonic::Bvs => {
+                // Branch if overflow set
+                next = self.branch(self.flags.v, operands[0], next);
+            }
+            Mnemonic::Clc => self.flags.trig_c_if(false),
+            Mnemonic::Cld => self.flags.trig_d_if(false),
+            Mnemonic::Cli => {
+                let old = self.flags.i;
+                self.flags.trig_i_if(false);
+                self.poll_old_i(old);
+            }
+            Mnemonic::Clv => self.flags.trig_v_if(false),
+            Mnemonic::Sec => self.flags.trig_c_if(true),
+            Mnemonic::Sed => self.flags.trig_d_if(true),
+            Mnemonic::Sei => {
+                let old = self.flags.i;
+                self.flags.trig_i_if(true);
+                self.poll_old_i(old);
+            }
+            Mnemonic::Brk => {
+                if self.brk_halts {
+                    return Outcome::Halted { pc, opcode };
                 }
+                // BRK skips a padding byte, the handler returns to PC + 2
+                self.interrupt(IRQ_VECTOR, self.pc.wrapping_add(2), true);
+                next = self.pc;
             }
-            "BIT" => {
-                // Bit test
-                let val = self.read(&instr.addr_mod, &instr.operands);
-                if !val.is_none() {
-                    self.flags.trig_z_if(self.a & val.unwrap() == 0);
-                    self.flags.trig_n_if(check_bit_one!(val.unwrap(), 7));
-                    self.flags.trig_v_if(check_bit_one!(val.unwrap(), 6));
+            Mnemonic::Nop if opcode == 0x5c && self.variant.is_cmos() => {
+                // The WDC part reads $FFxx and then keeps reading $FFFF
+                self.dummy_read(0xff00 | operands[0] as u16);
+                for _ in 0..4 {
+                    self.dummy_read(0xffff);
                 }
             }
-            "STA" => {
-                // Memory <- Accumulator
-                self.mem_write(&instr.addr_mod, &instr.operands, self.a);
+            Mnemonic::Nop => {
+                // The undocumented forms read their operand and drop it
+                if let Some((addr, _)) = self.resolve_addr(addr_mod, operands, false) {
+                    self.dummy_read(addr);
+                }
             }
-            "STX" => {
-                // Memory <- X
-                self.mem_write(&instr.addr_mod, &instr.operands, self.x);
+
+            // Undocumented NMOS instructions
+            Mnemonic::Slo | Mnemonic::Rla | Mnemonic::Sre | Mnemonic::Rra => {
+                // Read-modify-write shift combined with an accumulator op
+                let shift = match entry.mnemonic {
+                    Mnemonic::Slo => Mnemonic::Asl,
+                    Mnemonic::Rla => Mnemonic::Rol,
+                    Mnemonic::Sre => Mnemonic::Lsr,
+                    _ => Mnemonic::Ror,
+                };
+                if let Some(result) =
+                    self.modify(addr_mod, operands, true, |cpu, val| cpu.shift(shift, val))
+                {
+                    // ADC sets its own flags, NMOS decimal mode quirks included
+                    match entry.mnemonic {
+                        Mnemonic::Slo => self.a |= result,
+                        Mnemonic::Rla => self.a &= result,
+                        Mnemonic::Sre => self.a ^= result,
+                        _ => self.adc(result),
+                    }
+                    if entry.mnemonic != Mnemonic::Rra {
+                        self.set_zn(self.a);
+                    }
+                }
             }
-            "STY" => {
-                // Memory <- Y
-                self.mem_write(&instr.addr_mod, &instr.operands, self.y);
+            Mnemonic::Sax => {
+                self.mem_write(addr_mod, operands, self.a & self.x);
+            }
+            Mnemonic::Lax => {
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.a = val;
+                    self.x = val;
+                    self.set_zn(val);
+                }
+            }
+            Mnemonic::Dcp => {
+                if let Some(val) =
+                    self.modify(addr_mod, operands, true, |_, val| val.wrapping_sub(1))
+                {
+                    self.compare(self.a, val);
+                }
+            }
+            Mnemonic::Isc => {
+                if let Some(val) =
+                    self.modify(addr_mod, operands, true, |_, val| val.wrapping_add(1))
+                {
+                    self.sbc(val);
+                }
+            }
+            Mnemonic::Anc => {
+                self.a &= operands[0];
+                self.set_zn(self.a);
+                self.flags.trig_c_if(self.flags.n);
+            }
+            Mnemonic::Alr => {
+                self.a = self.shift(Mnemonic::Lsr, self.a & operands[0]);
+            }
+            Mnemonic::Arr => {
+                self.arr(operands[0]);
+            }
+            Mnemonic::Axs => {
+                // Compare style subtraction, no borrow in and no decimal mode
+                let val = self.a & self.x;
+                self.compare(val, operands[0]);
+                self.x = val.wrapping_sub(operands[0]);
+            }
+            Mnemonic::Las => {
+                if let Some(val) = self.read(addr_mod, operands) {
+                    let val = val & self.sp;
+                    self.a = val;
+                    self.x = val;
+                    self.sp = val;
+                    self.set_zn(val);
+                }
+            }
+            Mnemonic::Ane => {
+                // Unstable, uses the common $EE magic constant
+                self.a = (self.a | 0xee) & self.x & operands[0];
+                self.set_zn(self.a);
+            }
+            Mnemonic::Lxa => {
+                // Unstable, uses the common $EE magic constant
+                self.a = (self.a | 0xee) & operands[0];
+                self.x = self.a;
+                self.set_zn(self.a);
+            }
+            Mnemonic::Sha => {
+                self.store_and_high(addr_mod, operands, self.a & self.x, self.y);
+            }
+            Mnemonic::Shx => {
+                self.store_and_high(addr_mod, operands, self.x, self.y);
+            }
+            Mnemonic::Shy => {
+                self.store_and_high(addr_mod, operands, self.y, self.x);
+            }
+            Mnemonic::Tas => {
+                self.sp = self.a & self.x;
+                self.store_and_high(addr_mod, operands, self.sp, self.y);
+            }
+            Mnemonic::Jam => {
+                self.jammed = true;
+                return Outcome::Halted { pc, opcode };
             }
-            "" => {}
 
-            _ => println!("Invalid instruction"),
+            // 65C02 instructions
+            Mnemonic::Bra => {
+                // Branch always
+                next = self.branch(true, operands[0], next);
+            }
+            Mnemonic::Phx => {
+                self.push(self.x);
+            }
+            Mnemonic::Phy => {
+                self.push(self.y);
+            }
+            Mnemonic::Plx => {
+                self.dummy_read(STACK_BASE | self.sp as u16);
+                self.x = self.pull();
+                self.set_zn(self.x);
+            }
+            Mnemonic::Ply => {
+                self.dummy_read(STACK_BASE | self.sp as u16);
+                self.y = self.pull();
+                self.set_zn(self.y);
+            }
+            Mnemonic::Stz => {
+                // Memory <- 0
+                self.mem_write(addr_mod, operands, 0x00);
+            }
+            Mnemonic::Trb | Mnemonic::Tsb => {
+                // Z is set from A AND M before the bits are reset or set
+                self.modify(addr_mod, operands, true, |cpu, val| {
+                    cpu.flags.trig_z_if(cpu.a & val == 0);
+                    if entry.mnemonic == Mnemonic::Trb {
+                        val & !cpu.a
+                    } else {
+                        val | cpu.a
+                    }
+                });
+            }
+            Mnemonic::Rmb(bit) | Mnemonic::Smb(bit) => {
+                let mask = 1 << bit;
+                self.modify(addr_mod, operands, true, |_, val| {
+                    if let Mnemonic::Rmb(_) = entry.mnemonic {
+                        val & !mask
+                    } else {
+                        val | mask
+                    }
+                });
+            }
+            Mnemonic::Bbr(bit) | Mnemonic::Bbs(bit) => {
+                // Branch on a zero page bit, the offset is the second operand
+                if let Some(val) = self.read(addr_mod, operands) {
+                    self.dummy_read(operands[0] as u16);
+                    let set = val & 1 << bit != 0;
+                    let condition = if let Mnemonic::Bbs(_) = entry.mnemonic {
+                        set
+                    } else {
+                        !set
+                    };
+                    next = self.branch(condition, operands[1], next);
+                }
+            }
+            Mnemonic::Wai => {
+                // Sleeps until an interrupt line is asserted
+                self.waiting = true;
+                self.update_pc(next);
+                return Outcome::Waiting { pc, opcode };
+            }
+            Mnemonic::Stp => {
+                // Stops the clock, only a reset brings it back
+                self.jammed = true;
+                return Outcome::Halted { pc, opcode };
+            }
+
+            // 65C816 instructions run in execute816
+            Mnemonic::Brl
+            | Mnemonic::Jml
+            | Mnemonic::Jsl
+            | Mnemonic::Rtl
+            | Mnemonic::Cop
+            | Mnemonic::Wdm
+            | Mnemonic::Mvn
+            | Mnemonic::Mvp
+            | Mnemonic::Pea
+            | Mnemonic::Pei
+            | Mnemonic::Per
+            | Mnemonic::Phb
+            | Mnemonic::Phd
+            | Mnemonic::Phk
+            | Mnemonic::Plb
+            | Mnemonic::Pld
+            | Mnemonic::Rep
+            | Mnemonic::Sep
+            | Mnemonic::Tcd
+            | Mnemonic::Tcs
+            | Mnemonic::Tdc
+            | Mnemonic::Tsc
+            | Mnemonic::Txy
+            | Mnemonic::Tyx
+            | Mnemonic::Xba
+            | Mnemonic::Xce => return Outcome::IllegalOpcode { pc, opcode },
         }
 
         self.update_pc(next);
+
+        let fault = self.memory.take_fault();
+        match self.fault.take().or(fault) {
+            Some(addr) => Outcome::BusFault { pc, opcode, addr },
+            None => Outcome::Executed { pc, opcode },
+        }
     }
 
-    pub fn run(&mut self) {
+    pub fn run(&mut self, limits: &RunLimits) -> Outcome {
+        let started = Instant::now();
+        let start_cyc = self.cyc;
+        let mut count: u64 = 0;
+
         loop {
-            self.step();
-            if self.pc == self.rom.len() as u16 {
-                break;
+            let outcome = self.step();
+            if !outcome.is_ok() {
+                return outcome;
+            }
+            count += 1;
+
+            if limits.detect_traps && self.pc == outcome.pc() {
+                return Outcome::Trapped {
+                    pc: outcome.pc(),
+                    opcode: outcome.opcode(),
+                };
             }
+
+            let reason = if limits.stop_at == Some(self.pc) {
+                StopReason::Address
+            } else if limits
+                .stop_on
+                .is_some_and(|opcode| self.bus_peek(self.pc) == opcode)
+            {
+                StopReason::Opcode
+            } else if limits.stop_outside && !self.in_program(self.pc) {
+                StopReason::LeftProgram
+            } else if limits.max_instructions.is_some_and(|max| count >= max) {
+                StopReason::Instructions
+            } else if limits
+                .max_cycles
+                .is_some_and(|max| self.cyc - start_cyc >= max)
+            {
+                StopReason::Cycles
+            } else if limits
+                .interrupt
+                .is_some_and(|flag| flag.swap(false, Ordering::Relaxed))
+            {
+                StopReason::UserBreak
+            } else if count & 0x3ff == 0
+                && limits.timeout.is_some_and(|max| started.elapsed() >= max)
+            {
+                // Reading the clock is slow next to a step, only check now and then
+                StopReason::Timeout
+            } else {
+                continue;
+            };
+
+            return Outcome::Stopped {
+                pc: self.pc,
+                opcode: self.bus_peek(self.pc),
+                reason,
+            };
         }
     }
 
@@ -252,6 +1278,9 @@ impl CPU {
         self.show_y();
         self.show_pc();
         self.show_sp();
+        if self.variant.is_65816() {
+            self.show_816();
+        }
         println!("-----------------------");
         self.show_flags();
         self.show_memory();
@@ -259,7 +1288,7 @@ impl CPU {
     }
 
     pub fn show_flags(&self) {
-        println!("{:?}", self.flags);
+        println!("{:14} :=> {}", "Flags", self.flags.as_str());
     }
 
     pub fn show_accu(&self) {
@@ -282,11 +1311,707 @@ impl CPU {
         println!("{:14} :=> 0x{:0>2x}", "Stack Pointer", self.sp);
     }
 
+    pub fn show_816(&self) {
+        println!("{:14} :=> 0x{:0>4x}", "C Accumulator", self.c());
+        println!("{:14} :=> 0x{:0>4x}", "Index X", self.x16());
+        println!("{:14} :=> 0x{:0>4x}", "Index Y", self.y16());
+        println!("{:14} :=> 0x{:0>4x}", "Stack (16 bit)", self.sp16());
+        println!("{:14} :=> 0x{:0>4x}", "Direct Page", self.dp);
+        println!("{:14} :=> 0x{:0>2x}", "Data Bank", self.dbr);
+        println!("{:14} :=> 0x{:0>2x}", "Program Bank", self.pbr);
+        let mode = if self.emulation {
+            "Emulation"
+        } else {
+            "Native"
+        };
+        let width = |narrow: bool| if narrow { 8 } else { 16 };
+        println!(
+            "{:14} :=> {} (A {} bit, X/Y {} bit)",
+            "Mode",
+            mode,
+            width(self.flags.m),
+            width(self.flags.x)
+        );
+    }
+
     pub fn show_memory(&self) {
-        println!("{:?}", self.memory);
+        println!("{}", self.dump(0x0000, 0x00ff));
+    }
+
+    pub fn dump(&self, start: u16, end: u16) -> String {
+        let mut dump = String::new();
+        for row in (start as u32..=end as u32).step_by(16) {
+            dump.push_str(&format!("{:04x}:", row));
+            for addr in row..(row + 16).min(end as u32 + 1) {
+                dump.push_str(&format!(" {:02x}", self.bus_peek(addr as u16)));
+            }
+            dump.push('\n');
+        }
+        dump
     }
 
     pub fn show_cyc(&self) {
         println!("Cyc: {:?}", self.cyc);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{MappedBus, UninitPolicy};
+    use std::sync::atomic::AtomicBool;
+
+    // SED, then SEC or CLC, LDA #a and ADC or SBC #val, returning the CPU and
+    // the cycles the last instruction took
+    fn decimal(variant: Variant, op: u8, a: u8, val: u8, carry: bool) -> (CPU, u64) {
+        let set_carry = if carry { 0x38 } else { 0x18 };
+        let mut cpu = CPU::with_variant(variant, &[0xf8, set_carry, 0xa9, a, op, val], 0x0600);
+        for _ in 0..3 {
+            cpu.step();
+        }
+        let start = cpu.cyc;
+        cpu.step();
+        let cycles = cpu.cyc - start;
+        (cpu, cycles)
+    }
+
+    fn adc(variant: Variant, a: u8, val: u8, carry: bool) -> (CPU, u64) {
+        decimal(variant, 0x69, a, val, carry)
+    }
+
+    fn sbc(variant: Variant, a: u8, val: u8, carry: bool) -> (CPU, u64) {
+        decimal(variant, 0xe9, a, val, carry)
+    }
+
+    #[test]
+    fn decimal_add_carries_between_digits() {
+        let (cpu, _) = adc(Variant::Nmos6502, 0x09, 0x01, false);
+        assert_eq!((cpu.a, cpu.flags.c), (0x10, false));
+
+        let (cpu, _) = adc(Variant::Nmos6502, 0x99, 0x01, false);
+        assert_eq!((cpu.a, cpu.flags.c), (0x00, true));
+        // N comes from the sum before the high digit is adjusted, Z from the
+        // binary sum, so a zero result reads as negative and non-zero
+        assert_eq!(
+            (cpu.flags.n, cpu.flags.z, cpu.flags.v),
+            (true, false, false)
+        );
+
+        let (cpu, _) = adc(Variant::Nmos6502, 0x58, 0x46, true);
+        assert_eq!((cpu.a, cpu.flags.c), (0x05, true));
+    }
+
+    #[test]
+    fn decimal_add_nmos_flags() {
+        let (cpu, _) = adc(Variant::Nmos6502, 0x80, 0x80, false);
+        assert_eq!((cpu.a, cpu.flags.c), (0x60, true));
+        assert_eq!((cpu.flags.n, cpu.flags.z, cpu.flags.v), (false, true, true));
+
+        let (cpu, _) = adc(Variant::Nmos6502, 0x79, 0x00, true);
+        assert_eq!(cpu.a, 0x80);
+        assert_eq!((cpu.flags.n, cpu.flags.v), (true, true));
+    }
+
+    #[test]
+    fn decimal_subtract_borrows_between_digits() {
+        let (cpu, _) = sbc(Variant::Nmos6502, 0x10, 0x01, true);
+        assert_eq!((cpu.a, cpu.flags.c), (0x09, true));
+
+        let (cpu, _) = sbc(Variant::Nmos6502, 0x00, 0x01, true);
+        assert_eq!((cpu.a, cpu.flags.c), (0x99, false));
+        // Flags come from the binary difference 0xff
+        assert_eq!((cpu.flags.n, cpu.flags.z), (true, false));
+
+        let (cpu, _) = sbc(Variant::Wdc65C02, 0x10, 0x01, true);
+        assert_eq!((cpu.a, cpu.flags.c), (0x09, true));
+    }
+
+    #[test]
+    fn cmos_fixes_flags_up_in_an_extra_cycle() {
+        let (cpu, nmos) = adc(Variant::Nmos6502, 0x99, 0x01, false);
+        assert_eq!(nmos, 2);
+        assert!(cpu.flags.n);
+
+        let (cpu, cmos) = adc(Variant::Wdc65C02, 0x99, 0x01, false);
+        assert_eq!(cmos, 3);
+        assert_eq!((cpu.a, cpu.flags.c), (0x00, true));
+        assert_eq!((cpu.flags.n, cpu.flags.z), (false, true));
+
+        let (cpu, cmos) = adc(Variant::Wdc65C02, 0x80, 0x80, false);
+        assert_eq!((cpu.a, cpu.flags.z), (0x60, false));
+        assert_eq!(cmos, 3);
+
+        let (cpu, cmos) = sbc(Variant::Wdc65C02, 0x00, 0x01, true);
+        assert_eq!((cpu.a, cpu.flags.n), (0x99, true));
+        assert_eq!(cmos, 3);
+    }
+
+    #[test]
+    fn binary_when_decimal_mode_is_off() {
+        let (cpu, cycles) = adc(Variant::Ricoh2A03, 0x09, 0x01, false);
+        assert_eq!((cpu.a, cycles), (0x0a, 2));
+        let (cpu, _) = sbc(Variant::Ricoh2A03, 0x10, 0x01, true);
+        assert_eq!(cpu.a, 0x0f);
+
+        let mut cpu = CPU::new(&[0xf8, 0x18, 0xa9, 0x09, 0x69, 0x01], 0x0600);
+        cpu.decimal_mode = false;
+        for _ in 0..4 {
+            cpu.step();
+        }
+        assert_eq!(cpu.a, 0x0a);
+        assert!(cpu.flags.d);
+    }
+
+    // Runs the program at $0600 up to the BRK after it
+    fn run_to_brk(variant: Variant, program: &[u8]) -> CPU {
+        let mut cpu = CPU::with_variant(variant, program, 0x0600);
+        cpu.brk_halts = true;
+        let outcome = cpu.run(&RunLimits::new());
+        assert!(
+            matches!(outcome, Outcome::Halted { opcode: 0x00, .. }),
+            "{}",
+            outcome
+        );
+        cpu
+    }
+
+    #[test]
+    fn official_instructions_run_a_small_program() {
+        let program = [
+            0xa2, 0x03, // LDX #3
+            0xa9, 0x00, // LDA #0
+            0x18, // loop: CLC
+            0x69, 0x05, // ADC #5
+            0xca, // DEX
+            0xd0, 0xfa, // BNE loop
+            0x85, 0x10, // STA $10
+            0xe6, 0x10, // INC $10
+            0xa4, 0x10, // LDY $10
+            0xc0, 0x10, // CPY #$10
+            0x0a, // ASL A
+            0x29, 0x0f, // AND #$0F
+            0x49, 0xff, // EOR #$FF
+        ];
+        let cpu = run_to_brk(Variant::Nmos6502, &program);
+        assert_eq!((cpu.a, cpu.x, cpu.y), (0xf1, 0x00, 0x10));
+        assert_eq!(cpu.bus_peek(0x0010), 0x10);
+        assert_eq!(
+            (cpu.flags.n, cpu.flags.z, cpu.flags.c),
+            (true, false, false)
+        );
+    }
+
+    #[test]
+    fn code_is_fetched_from_memory_at_the_origin() {
+        // STA rewrites the NOP after it into INX before it is fetched
+        let program = [0xa9, 0xe8, 0x8d, 0x06, 0x06, 0xea, 0xea];
+        assert_eq!(CPU::new(&program, 0x0600).pc, 0x0600);
+        let cpu = run_to_brk(Variant::Nmos6502, &program);
+        assert_eq!((cpu.x, cpu.pc), (0x01, 0x0607));
+    }
+
+    #[test]
+    fn stack_lives_in_page_one() {
+        let program = [
+            0xa9, 0x42, // LDA #$42
+            0x48, // PHA
+            0xa9, 0x00, // LDA #0
+            0x08, // PHP
+            0x68, // PLA
+            0x85, 0x10, // STA $10
+            0x68, // PLA
+            0x20, 0x0e, 0x06, // JSR sub
+            0x00, // BRK
+            0xe8, // sub: INX
+            0x60, // RTS
+        ];
+        let cpu = run_to_brk(Variant::Nmos6502, &program);
+        assert_eq!((cpu.a, cpu.x, cpu.sp, cpu.pc), (0x42, 0x01, 0xfd, 0x060d));
+        // PHP pushed B and the always set bit with Z and I, JSR the address
+        // of its own last byte
+        assert_eq!(cpu.bus_peek(0x0010), 0x36);
+        let stack = [0x01fc, 0x01fd].map(|addr| cpu.bus_peek(addr));
+        assert_eq!(stack, [0x0c, 0x06]);
+
+        // LDX #0, TXS, LDA #$77, PHA wraps S around within the page
+        let cpu = run_to_brk(Variant::Nmos6502, &[0xa2, 0x00, 0x9a, 0xa9, 0x77, 0x48]);
+        assert_eq!((cpu.sp, cpu.bus_peek(0x0100)), (0xff, 0x77));
+    }
+
+    #[test]
+    fn nmos_indirect_jmp_stays_in_the_pointer_page() {
+        // JMP ($06FF) takes its high byte from $0600, the JMP opcode itself
+        let mut cpu = CPU::new(&[0x6c, 0xff, 0x06], 0x0600);
+        cpu.memory.load(0x06ff, &[0x00, 0x08]);
+        cpu.step();
+        assert_eq!(cpu.pc, 0x6c00);
+    }
+
+    #[test]
+    fn zero_page_indexing_wraps() {
+        // LDX #$10, LDA #$55, STA $F8,X
+        let cpu = run_to_brk(Variant::Nmos6502, &[0xa2, 0x10, 0xa9, 0x55, 0x95, 0xf8]);
+        assert_eq!((cpu.bus_peek(0x0008), cpu.bus_peek(0x0108)), (0x55, 0x00));
+    }
+
+    #[test]
+    fn mapped_bus_mirrors_ram_and_keeps_rom() {
+        let mut rom = vec![0xea; 0x1000];
+        // LDA #$AA, STA $0800, STA $F000, LDX $0000
+        let program = [
+            0xa9, 0xaa, 0x8d, 0x00, 0x08, 0x8d, 0x00, 0xf0, 0xae, 0x00, 0x00,
+        ];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x0ffc..0x0ffe].copy_from_slice(&[0x00, 0xf0]);
+        let mut bus = MappedBus::new();
+        bus.map_ram(0x0000, 0x1fff, 0x0800)
+            .map_rom(0xf000, 0xffff, rom);
+
+        // Starts from the vector in the ROM, not the load origin
+        let mut cpu = CPU::with_bus(bus, Variant::Nmos6502, &[], 0x0200);
+        assert_eq!(cpu.pc, 0xf000);
+        for _ in 0..4 {
+            cpu.step();
+        }
+        assert_eq!(cpu.x, 0xaa);
+        assert_eq!(cpu.bus_peek(0xf000), 0xa9);
+    }
+
+    #[test]
+    fn fault_policy_stops_on_uninitialized_reads() {
+        let mut memory = Memory::with_policy(UninitPolicy::Fault);
+        memory.load(RESET_VECTOR, &[0x00, 0x06]);
+        // NOP, then LDA $2000 from memory nothing wrote
+        let mut cpu = CPU::with_bus(memory, Variant::Nmos6502, &[0xea, 0xad, 0x00, 0x20], 0x0600);
+        assert_eq!(
+            cpu.run(&RunLimits::new()),
+            Outcome::BusFault {
+                pc: 0x0601,
+                opcode: 0xad,
+                addr: 0x2000
+            }
+        );
+    }
+
+    #[test]
+    fn random_policy_is_seeded_and_spares_written_cells() {
+        let garbage = |seed| {
+            let mut memory = Memory::new();
+            memory.poke(0x2000, 0x42);
+            memory.set_policy(UninitPolicy::Random(seed));
+            (memory.peek(0x2000), memory.peek(0x2001))
+        };
+        assert_eq!(garbage(7), garbage(7));
+        assert_eq!(garbage(7).0, 0x42);
+    }
+
+    #[test]
+    fn step_reports_what_happened() {
+        // NOP with a breakpoint on it, then BRK set to halt
+        let mut cpu = CPU::new(&[0xea, 0x00], 0x0600);
+        cpu.breakpoints.push(0x0600);
+        cpu.brk_halts = true;
+        let nop = (0x0600, 0xea);
+        assert_eq!(
+            cpu.step(),
+            Outcome::Breakpoint {
+                pc: nop.0,
+                opcode: nop.1
+            }
+        );
+        assert_eq!(
+            cpu.step(),
+            Outcome::Executed {
+                pc: nop.0,
+                opcode: nop.1
+            }
+        );
+        // A halt leaves PC on the BRK
+        let brk = Outcome::Halted {
+            pc: 0x0601,
+            opcode: 0x00,
+        };
+        assert_eq!(cpu.step(), brk);
+        assert_eq!(cpu.step(), brk);
+        assert!(!brk.is_ok());
+    }
+
+    #[test]
+    fn run_stops_on_its_limits() {
+        // loop: INX, JMP loop
+        let stopped = |limits: RunLimits| {
+            let mut cpu = CPU::new(&[0xe8, 0x4c, 0x00, 0x06], 0x0600);
+            match cpu.run(&limits) {
+                Outcome::Stopped { pc, reason, .. } => (reason, pc, cpu.x),
+                outcome => panic!("{}", outcome),
+            }
+        };
+        let limits = RunLimits {
+            max_instructions: Some(5),
+            ..RunLimits::new()
+        };
+        assert_eq!(stopped(limits), (StopReason::Instructions, 0x0601, 3));
+        // INX takes 2 cycles and JMP 3
+        let limits = RunLimits {
+            max_cycles: Some(10),
+            ..RunLimits::new()
+        };
+        assert_eq!(stopped(limits), (StopReason::Cycles, 0x0600, 2));
+        let limits = RunLimits {
+            stop_at: Some(0x0601),
+            ..RunLimits::new()
+        };
+        assert_eq!(stopped(limits), (StopReason::Address, 0x0601, 1));
+        let limits = RunLimits {
+            stop_on: Some(0xe8),
+            ..RunLimits::new()
+        };
+        assert_eq!(stopped(limits), (StopReason::Opcode, 0x0600, 1));
+
+        static INTERRUPTED: AtomicBool = AtomicBool::new(true);
+        let limits = RunLimits {
+            interrupt: Some(&INTERRUPTED),
+            ..RunLimits::new()
+        };
+        assert_eq!(stopped(limits), (StopReason::UserBreak, 0x0601, 1));
+        assert!(!INTERRUPTED.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn run_catches_traps_and_leaving_the_program() {
+        let limits = RunLimits {
+            detect_traps: true,
+            stop_outside: true,
+            ..RunLimits::new()
+        };
+        // JMP *
+        let mut cpu = CPU::new(&[0x4c, 0x00, 0x06], 0x0600);
+        let trapped = Outcome::Trapped {
+            pc: 0x0600,
+            opcode: 0x4c,
+        };
+        assert_eq!(cpu.run(&limits), trapped);
+        // JMP $0700
+        let mut cpu = CPU::new(&[0x4c, 0x00, 0x07], 0x0600);
+        assert!(matches!(
+            cpu.run(&limits),
+            Outcome::Stopped {
+                pc: 0x0700,
+                reason: StopReason::LeftProgram,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn undocumented_nmos_instructions() {
+        let program = [
+            0xa9, 0x5a, // LDA #$5A
+            0x85, 0x10, // STA $10
+            0xa9, 0x00, // LDA #0
+            0xa7, 0x10, // LAX $10
+            0xa2, 0x0f, // LDX #$0F
+            0x87, 0x11, // SAX $11
+            0xc7, 0x11, // DCP $11
+            0xe7, 0x11, // ISC $11
+            0x07, 0x11, // SLO $11
+            0x4b, 0x0f, // ALR #$0F
+            0xcb, 0x01, // AXS #$01
+        ];
+        let cpu = run_to_brk(Variant::Nmos6502, &program);
+        // $11 goes $0A, $09, $0A, $14 while A goes $5A, $50, $54, $02
+        assert_eq!((cpu.a, cpu.x, cpu.bus_peek(0x0011)), (0x02, 0x01, 0x14));
+        assert!(cpu.flags.c);
+    }
+
+    #[test]
+    fn jam_holds_until_reset() {
+        let mut cpu = CPU::new(&[0x02], 0x0600);
+        let jam = Outcome::Halted {
+            pc: 0x0600,
+            opcode: 0x02,
+        };
+        assert_eq!(cpu.step(), jam);
+        cpu.set_nmi(true);
+        assert_eq!(cpu.step(), jam);
+        cpu.reset();
+        assert!(!cpu.jammed);
+    }
+
+    #[test]
+    fn cmos_indirect_jmp_crosses_the_page() {
+        let mut cpu = CPU::with_variant(Variant::Wdc65C02, &[0x6c, 0xff, 0x06], 0x0600);
+        cpu.memory.load(0x06ff, &[0x00, 0x08]);
+        cpu.step();
+        assert_eq!(cpu.pc, 0x0800);
+        // The fix costs a cycle
+        let cmos = cycles_of(Variant::Wdc65C02, &[0x6c, 0x00, 0x00], 1);
+        assert_eq!(
+            cmos,
+            cycles_of(Variant::Nmos6502, &[0x6c, 0x00, 0x00], 1) + 1
+        );
+    }
+
+    #[test]
+    fn cmos_instructions() {
+        let program = [
+            0xa9, 0xff, // LDA #$FF
+            0x85, 0x10, // STA $10
+            0x64, 0x10, // STZ $10
+            0x1a, // INC A
+            0xda, // PHX
+            0xa2, 0x07, // LDX #7
+            0xfa, // PLX
+            0x87, 0x10, // SMB0 $10
+            0x8f, 0x10, 0x01, // BBS0 $10, +1
+            0xe8, // INX
+            0x80, 0x01, // BRA +1
+            0xe8, // INX
+        ];
+        let cpu = run_to_brk(Variant::Wdc65C02, &program);
+        assert_eq!((cpu.a, cpu.x, cpu.bus_peek(0x0010)), (0x00, 0x00, 0x01));
+        assert_eq!(cpu.sp, 0xfd);
+    }
+
+    #[test]
+    fn mos6510_port_mixes_outputs_and_inputs() {
+        let program = [
+            0xa9, 0x07, // LDA #$07
+            0x85, 0x00, // STA $00
+            0xa9, 0x05, // LDA #$05
+            0x85, 0x01, // STA $01
+            0xa5, 0x01, // LDA $01
+        ];
+        let cpu = run_to_brk(Variant::Mos6510, &program);
+        // The low three pins drive $05, the rest are inputs pulled high
+        assert_eq!(cpu.a, 0xfd);
+        assert_eq!(cpu.port.map(|port| port.ddr), Some(0x07));
+    }
+
+    #[test]
+    fn mos6507_drops_the_top_address_lines() {
+        // LDA #$42, STA $2010, LDX $0010
+        let program = [0xa9, 0x42, 0x8d, 0x10, 0x20, 0xae, 0x10, 0x00];
+        let cpu = run_to_brk(Variant::Mos6507, &program);
+        assert_eq!(cpu.x, 0x42);
+        assert_eq!(cpu.bus_peek(0xe010), 0x42);
+    }
+
+    #[test]
+    fn wdc65c816_native_mode_widens_registers() {
+        let program = [
+            0x18, // CLC
+            0xfb, // XCE
+            0xc2, 0x30, // REP #$30
+            0xa9, 0x34, 0x12, // LDA #$1234
+            0xa2, 0xcd, 0xab, // LDX #$ABCD
+            0x8f, 0x00, 0x00, 0x01, // STA $010000
+            0xaf, 0x00, 0x00, 0x00, // LDA $000000
+        ];
+        let cpu = run_to_brk(Variant::Wdc65C816, &program);
+        assert!(!cpu.emulation);
+        // Bank $01 is its own memory, not a mirror of bank $00
+        assert_eq!((cpu.c(), cpu.x16()), (0x0000, 0xabcd));
+        let stored = [0x010000, 0x010001].map(|addr| cpu.bus_peek_long(addr));
+        assert_eq!(stored, [0x34, 0x12]);
+    }
+
+    #[test]
+    fn opcode_tables_encode_what_they_decode() {
+        for variant in Variant::ALL {
+            for opcode in 0..=0xff {
+                let entry = optable::lookup(variant, opcode);
+                let encoded = optable::encode(variant, entry.mnemonic, entry.addr_mod)
+                    .map(|opcode| optable::lookup(variant, opcode));
+                assert_eq!(
+                    encoded.map(|other| (other.mnemonic, other.addr_mod)),
+                    Some((entry.mnemonic, entry.addr_mod)),
+                    "{} 0x{:0>2x}",
+                    variant.as_str(),
+                    opcode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mnemonics_parse_back_from_their_names() {
+        for mnemonic in Mnemonic::ALL {
+            let name = mnemonic.as_str().to_ascii_lowercase();
+            assert_eq!(name.parse(), Ok(mnemonic));
+        }
+        assert_eq!("Bbs7".parse(), Ok(Mnemonic::Bbs(7)));
+        assert!("rmb8".parse::<Mnemonic>().is_err());
+
+        // The bit number of the decoded instruction shows in its name
+        let cpu = CPU::with_variant(Variant::Wdc65C02, &[0xa9, 0x01, 0xf7, 0x10], 0x0600);
+        assert_eq!(
+            cpu.disassemble(0x0600, 0x0604),
+            "  1: LDA #$01\n  2: SMB7 $10\n"
+        );
+    }
+
+    #[test]
+    fn flags_pack_into_the_status_byte() {
+        let flags = Flags::from(0xc3);
+        assert_eq!(
+            (flags.n, flags.v, flags.z, flags.c),
+            (true, true, true, true)
+        );
+        assert_eq!((flags.b, flags.d, flags.i), (false, false, false));
+        // Bit 5 always reads as set
+        assert_eq!(u8::from(flags), 0xe3);
+        assert_eq!(flags.as_str(), "NV-bdiZC");
+        assert_eq!((flags.pushed(true), flags.pushed(false)), (0xf3, 0xe3));
+
+        // PLP leaves B as it was
+        let mut flags = Flags::new();
+        flags.pull(0xff);
+        assert_eq!(flags.as_str(), "NV-bDIZC");
+    }
+
+    // Cycles the last of the first n instructions of the program takes
+    fn cycles_of(variant: Variant, program: &[u8], n: usize) -> u64 {
+        let mut cpu = CPU::with_variant(variant, program, 0x0600);
+        for _ in 1..n {
+            cpu.step();
+        }
+        let start = cpu.cyc;
+        cpu.step();
+        cpu.cyc - start
+    }
+
+    #[test]
+    fn reset_takes_seven_cycles() {
+        assert_eq!(CPU::new(&[0xea], 0x0600).cyc, 7);
+    }
+
+    #[test]
+    fn indexed_reads_pay_for_crossing_a_page() {
+        let nmos = Variant::Nmos6502;
+        // LDX #1, then LDA abs,X on either side of a page boundary
+        assert_eq!(cycles_of(nmos, &[0xa2, 0x01, 0xbd, 0xfe, 0x06], 2), 4);
+        assert_eq!(cycles_of(nmos, &[0xa2, 0x01, 0xbd, 0xff, 0x06], 2), 5);
+        // Stores always take the extra cycle
+        assert_eq!(cycles_of(nmos, &[0xa2, 0x01, 0x9d, 0xfe, 0x06], 2), 5);
+        // LDA ($10),Y with the pointer at $00FF
+        let program = [0xa9, 0xff, 0x85, 0x10, 0xa0, 0x01, 0xb1, 0x10];
+        assert_eq!(cycles_of(nmos, &program, 4), 6);
+    }
+
+    #[test]
+    fn taken_branches_pay_per_page() {
+        let nmos = Variant::Nmos6502;
+        // LDX #0 or #1 decides whether BNE is taken
+        assert_eq!(cycles_of(nmos, &[0xa2, 0x00, 0xd0, 0x02], 2), 2);
+        assert_eq!(cycles_of(nmos, &[0xa2, 0x01, 0xd0, 0x02], 2), 3);
+        // Back 128 bytes from $0604 lands on page $05
+        assert_eq!(cycles_of(nmos, &[0xa2, 0x01, 0xd0, 0x80], 2), 4);
+    }
+
+    // The program at $0600 with NOPs for the IRQ handler at $0700 and the
+    // NMI handler at $0780
+    fn with_handlers(program: &[u8]) -> CPU {
+        let mut cpu = CPU::new(program, 0x0600);
+        cpu.memory.load(IRQ_VECTOR, &[0x00, 0x07]);
+        cpu.memory.load(NMI_VECTOR, &[0x80, 0x07]);
+        cpu.memory.load(0x0700, &[0xea; 4]);
+        cpu.memory.load(0x0780, &[0xea; 4]);
+        cpu
+    }
+
+    #[test]
+    fn irq_waits_for_the_instruction_after_cli() {
+        let mut cpu = with_handlers(&[0x58, 0xea, 0xea]);
+        cpu.set_irq(true);
+        assert_eq!(
+            cpu.step(),
+            Outcome::Executed {
+                pc: 0x0600,
+                opcode: 0x58
+            }
+        );
+        assert_eq!(
+            cpu.step(),
+            Outcome::Executed {
+                pc: 0x0601,
+                opcode: 0xea
+            }
+        );
+        assert!(matches!(
+            cpu.step(),
+            Outcome::Interrupt {
+                vector: IRQ_VECTOR,
+                ..
+            }
+        ));
+        assert_eq!(cpu.pc, 0x0700);
+        // Returns to the instruction it cut in before, B clear in the status
+        let pushed = [0x01fb, 0x01fc, 0x01fd].map(|addr| cpu.bus_peek(addr));
+        assert_eq!(pushed, [0x20, 0x02, 0x06]);
+    }
+
+    #[test]
+    fn sei_lets_one_more_irq_through() {
+        let mut cpu = with_handlers(&[0x58, 0x78, 0xea]);
+        cpu.step();
+        cpu.set_irq(true);
+        assert_eq!(
+            cpu.step(),
+            Outcome::Executed {
+                pc: 0x0601,
+                opcode: 0x78
+            }
+        );
+        assert!(matches!(
+            cpu.step(),
+            Outcome::Interrupt {
+                vector: IRQ_VECTOR,
+                ..
+            }
+        ));
+        // The handler runs with I set, so the held line doesn't fire again
+        assert_eq!(
+            cpu.step(),
+            Outcome::Executed {
+                pc: 0x0700,
+                opcode: 0xea
+            }
+        );
+    }
+
+    #[test]
+    fn nmi_wins_over_irq_and_fires_on_its_edge() {
+        let mut cpu = with_handlers(&[0x58, 0xea, 0xea]);
+        cpu.step();
+        cpu.step();
+        cpu.set_irq(true);
+        cpu.set_nmi(true);
+        assert!(matches!(
+            cpu.step(),
+            Outcome::Interrupt {
+                vector: NMI_VECTOR,
+                ..
+            }
+        ));
+        assert_eq!(cpu.pc, 0x0780);
+        // Holding NMI down doesn't fire it again, releasing it and pulling
+        // it down once more does, even with I set
+        assert_eq!(
+            cpu.step(),
+            Outcome::Executed {
+                pc: 0x0780,
+                opcode: 0xea
+            }
+        );
+        cpu.set_nmi(false);
+        cpu.set_nmi(true);
+        assert!(matches!(
+            cpu.step(),
+            Outcome::Interrupt {
+                vector: NMI_VECTOR,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,908 @@
+use crate::addrmod::AddrMod;
+use crate::cpu::CPU;
+use crate::memory::Bus;
+use crate::mnemonic::Mnemonic;
+use crate::outcome::Outcome;
+use crate::variant::Widths;
+use crate::{cycles, optable};
+
+pub const COP_VECTOR: u16 = 0xfff4;
+pub const NATIVE_COP_VECTOR: u16 = 0xffe4;
+pub const NATIVE_BRK_VECTOR: u16 = 0xffe6;
+// Native mode NMI and IRQ vectors sit just below their emulation mode ones
+pub const NATIVE_VECTOR_OFFSET: u16 = 0x10;
+
+// The WDC 65C816 on top of the shared CPU state. The 6502 registers hold the
+// low bytes, the high bytes of C, X, Y and S live in ah, xh, yh and sph. In
+// emulation mode M and X stay set and the stack stays in page $01.
+impl<B: Bus> CPU<B> {
+    pub fn reset816(&mut self) {
+        self.emulation = true;
+        self.flags.trig_m_if(true);
+        self.flags.trig_x_if(true);
+        self.xh = 0x00;
+        self.yh = 0x00;
+        self.sph = 0x01;
+        self.dp = 0x0000;
+        self.dbr = 0x00;
+        self.pbr = 0x00;
+    }
+
+    pub fn widths(&self) -> Widths {
+        if !self.variant.is_65816() {
+            return Widths::default();
+        }
+        Widths {
+            wide_a: !self.flags.m,
+            wide_index: !self.flags.x,
+        }
+    }
+
+    pub fn c(&self) -> u16 {
+        u16::from_le_bytes([self.a, self.ah])
+    }
+
+    pub fn set_c(&mut self, val: u16) {
+        [self.a, self.ah] = val.to_le_bytes();
+    }
+
+    pub fn x16(&self) -> u16 {
+        u16::from_le_bytes([self.x, self.xh])
+    }
+
+    pub fn y16(&self) -> u16 {
+        u16::from_le_bytes([self.y, self.yh])
+    }
+
+    pub fn sp16(&self) -> u16 {
+        u16::from_le_bytes([self.sp, self.sph])
+    }
+
+    pub fn set_sp16(&mut self, val: u16) {
+        [self.sp, self.sph] = val.to_le_bytes();
+        if self.emulation {
+            self.sph = 0x01;
+        }
+    }
+
+    fn acc(&self) -> u16 {
+        if self.flags.m {
+            self.a as u16
+        } else {
+            self.c()
+        }
+    }
+
+    fn set_acc(&mut self, val: u16) {
+        // With an 8 bit accumulator B keeps its value
+        if self.flags.m {
+            self.a = val as u8;
+        } else {
+            self.set_c(val);
+        }
+    }
+
+    fn set_index_x(&mut self, val: u16) {
+        if self.flags.x {
+            self.x = val as u8;
+        } else {
+            [self.x, self.xh] = val.to_le_bytes();
+        }
+    }
+
+    fn set_index_y(&mut self, val: u16) {
+        if self.flags.x {
+            self.y = val as u8;
+        } else {
+            [self.y, self.yh] = val.to_le_bytes();
+        }
+    }
+
+    fn set_nz(&mut self, val: u16, wide: bool) {
+        if wide {
+            self.flags.trig_z_if(val == 0);
+            self.flags.trig_n_if(val & 0x8000 != 0);
+        } else {
+            self.set_zn(val as u8);
+        }
+    }
+
+    pub fn status816(&self) -> u8 {
+        // Native mode reuses bits 5 and 4 for M and X, there is no B flag
+        if self.emulation {
+            self.status()
+        } else {
+            self.status() & !0x30 | (self.flags.m as u8) << 5 | (self.flags.x as u8) << 4
+        }
+    }
+
+    pub fn set_status816(&mut self, status: u8) {
+        self.set_status(status);
+        if !self.emulation {
+            self.flags.trig_m_if(status & 0x20 != 0);
+            self.flags.trig_x_if(status & 0x10 != 0);
+            if self.flags.x {
+                // Narrowing the index registers drops their high bytes
+                self.xh = 0x00;
+                self.yh = 0x00;
+            }
+        }
+    }
+
+    pub fn bus_read_long(&mut self, addr: u32) -> u8 {
+        match addr {
+            0x0000..=0xffff => self.bus_read(addr as u16),
+            _ => self.memory.read_long(addr & 0xffffff),
+        }
+    }
+
+    pub fn bus_write_long(&mut self, addr: u32, val: u8) {
+        match addr {
+            0x0000..=0xffff => self.bus_write(addr as u16, val),
+            _ => self.memory.write_long(addr & 0xffffff, val),
+        }
+    }
+
+    pub fn bus_peek_long(&self, addr: u32) -> u8 {
+        match addr {
+            0x0000..=0xffff => self.bus_peek(addr as u16),
+            _ => self.memory.peek_long(addr & 0xffffff),
+        }
+    }
+
+    fn read_data(&mut self, addr: u32, wide: bool) -> u16 {
+        let lo = self.bus_read_long(addr);
+        if !wide {
+            return lo as u16;
+        }
+        let hi = self.bus_read_long(addr.wrapping_add(1) & 0xffffff);
+        u16::from_le_bytes([lo, hi])
+    }
+
+    fn write_data(&mut self, addr: u32, val: u16, wide: bool) {
+        let [lo, hi] = val.to_le_bytes();
+        self.bus_write_long(addr, lo);
+        if wide {
+            self.bus_write_long(addr.wrapping_add(1) & 0xffffff, hi);
+        }
+    }
+
+    fn push816(&mut self, val: u8) {
+        if self.emulation {
+            self.push(val);
+        } else {
+            let sp = self.sp16();
+            self.bus_write(sp, val);
+            self.set_sp16(sp.wrapping_sub(1));
+        }
+    }
+
+    fn pull816(&mut self) -> u8 {
+        if self.emulation {
+            self.pull()
+        } else {
+            let sp = self.sp16().wrapping_add(1);
+            self.set_sp16(sp);
+            self.bus_read(sp)
+        }
+    }
+
+    fn push816_word(&mut self, val: u16) {
+        self.push816((val >> 8) as u8);
+        self.push816(val as u8);
+    }
+
+    fn pull816_word(&mut self) -> u16 {
+        let lo = self.pull816();
+        let hi = self.pull816();
+        u16::from_le_bytes([lo, hi])
+    }
+
+    fn push_data(&mut self, val: u16, wide: bool) {
+        if wide {
+            self.push816_word(val);
+        } else {
+            self.push816(val as u8);
+        }
+    }
+
+    fn pull_data(&mut self, wide: bool) -> u16 {
+        if wide {
+            self.pull816_word()
+        } else {
+            self.pull816() as u16
+        }
+    }
+
+    fn direct(&self, offset: u8, index: u16) -> u16 {
+        // A page aligned direct page wraps like the zero page in emulation mode
+        if self.emulation && self.dp & 0xff == 0 {
+            self.dp | ((offset as u16).wrapping_add(index) & 0xff)
+        } else {
+            self.dp.wrapping_add(offset as u16).wrapping_add(index)
+        }
+    }
+
+    fn direct_word(&mut self, offset: u8, index: u16) -> u16 {
+        let lo = self.bus_read(self.direct(offset, index));
+        let hi = self.bus_read(self.direct(offset, index.wrapping_add(1)));
+        u16::from_le_bytes([lo, hi])
+    }
+
+    fn direct_long(&mut self, offset: u8) -> u32 {
+        let word = self.direct_word(offset, 0);
+        let bank = self.bus_read(self.direct(offset, 2));
+        (bank as u32) << 16 | word as u32
+    }
+
+    pub fn resolve816(&mut self, addr_mod: &AddrMod, operands: &[u8]) -> Option<(u32, bool)> {
+        // Returns the 24 bit effective address and whether indexing crossed a page
+        fn indexed(base: u32, index: u16) -> (u32, bool) {
+            let addr = base.wrapping_add(index as u32) & 0xffffff;
+            (addr, base & 0xffff00 != addr & 0xffff00)
+        }
+
+        let data_bank = (self.dbr as u32) << 16;
+        let program_bank = (self.pbr as u32) << 16;
+        let word = || u16::from_le_bytes([operands[0], operands[1]]);
+        let long = || u32::from_le_bytes([operands[0], operands[1], operands[2], 0]);
+
+        let resolved = match addr_mod {
+            AddrMod::ZeroPage => (self.direct(operands[0], 0) as u32, false),
+            AddrMod::ZeroPageX => (self.direct(operands[0], self.x16()) as u32, false),
+            AddrMod::ZeroPageY => (self.direct(operands[0], self.y16()) as u32, false),
+            AddrMod::Absolute => (data_bank | word() as u32, false),
+            AddrMod::AbsoluteX => indexed(data_bank | word() as u32, self.x16()),
+            AddrMod::AbsoluteY => indexed(data_bank | word() as u32, self.y16()),
+            AddrMod::AbsoluteLong => (long(), false),
+            AddrMod::AbsoluteLongX => indexed(long(), self.x16()),
+            AddrMod::IndirectX => {
+                let ptr = self.direct_word(operands[0], self.x16());
+                (data_bank | ptr as u32, false)
+            }
+            AddrMod::IndirectY => {
+                let ptr = self.direct_word(operands[0], 0);
+                indexed(data_bank | ptr as u32, self.y16())
+            }
+            AddrMod::ZeroPageIndirect => {
+                let ptr = self.direct_word(operands[0], 0);
+                (data_bank | ptr as u32, false)
+            }
+            AddrMod::DirectIndirectLong => (self.direct_long(operands[0]), false),
+            AddrMod::DirectIndirectLongY => {
+                let ptr = self.direct_long(operands[0]);
+                indexed(ptr, self.y16())
+            }
+            AddrMod::StackRelative => (self.sp16().wrapping_add(operands[0] as u16) as u32, false),
+            AddrMod::StackRelativeIndirectY => {
+                let ptr = self.read_word(self.sp16().wrapping_add(operands[0] as u16));
+                indexed(data_bank | ptr as u32, self.y16())
+            }
+            // Jump targets, the pointers of JMP (abs) and JML [abs] live in
+            // bank $00 while JMP (abs,X) reads its pointer from the program bank
+            AddrMod::Indirect => (program_bank | self.read_word(word()) as u32, false),
+            AddrMod::AbsoluteIndirectX => {
+                let ptr = program_bank | word().wrapping_add(self.x16()) as u32;
+                let target = self.read_data(ptr, true);
+                (program_bank | target as u32, false)
+            }
+            AddrMod::AbsoluteIndirectLong => {
+                let target = self.read_word(word()) as u32;
+                let bank = self.bus_read(word().wrapping_add(2)) as u32;
+                (bank << 16 | target, false)
+            }
+            _ => return None,
+        };
+        Some(resolved)
+    }
+
+    fn operand816(&mut self, addr_mod: &AddrMod, operands: &[u8], wide: bool) -> Option<u16> {
+        match addr_mod {
+            AddrMod::Immediate => Some(operands[0] as u16),
+            AddrMod::ImmediateWord => Some(operand_word(operands)),
+            AddrMod::Accumulator => Some(self.acc()),
+            _ => {
+                let (addr, _) = self.resolve816(addr_mod, operands)?;
+                Some(self.read_data(addr, wide))
+            }
+        }
+    }
+
+    fn store816(&mut self, addr_mod: &AddrMod, operands: &[u8], val: u16, wide: bool) {
+        match addr_mod {
+            AddrMod::Accumulator => self.set_acc(val),
+            _ => {
+                if let Some((addr, _)) = self.resolve816(addr_mod, operands) {
+                    self.write_data(addr, val, wide);
+                }
+            }
+        }
+    }
+
+    fn adc816(&mut self, val: u16, wide: bool) {
+        let (acc, carry) = (self.acc() as u32, self.flags.c as u32);
+        let (mask, sign, digits) = if wide {
+            (0xffff, 0x8000, 4)
+        } else {
+            (0xff, 0x80, 2)
+        };
+
+        let binary = acc + val as u32 + carry;
+        let result = if self.flags.d && self.decimal_mode {
+            // One BCD digit at a time, carrying into the next nibble
+            let (mut result, mut carry) = (0, carry);
+            for shift in (0..digits).map(|digit| digit * 4) {
+                let mut sum = (acc >> shift & 0xf) + (val as u32 >> shift & 0xf) + carry;
+                if sum > 9 {
+                    sum += 6;
+                }
+                carry = (sum > 0xf) as u32;
+                result |= (sum & 0xf) << shift;
+            }
+            result | carry << (digits * 4)
+        } else {
+            binary
+        };
+
+        self.flags
+            .trig_v_if((acc ^ result) & (val as u32 ^ result) & sign != 0);
+        self.flags.trig_c_if(result > mask);
+        self.set_acc((result & mask) as u16);
+        self.set_nz((result & mask) as u16, wide);
+    }
+
+    fn sbc816(&mut self, val: u16, wide: bool) {
+        let (acc, carry) = (self.acc() as u32, self.flags.c as u32);
+        let (mask, sign, digits) = if wide {
+            (0xffff, 0x8000, 4)
+        } else {
+            (0xff, 0x80, 2)
+        };
+
+        // V always comes from the binary difference
+        let binary = acc + (!val as u32 & mask) + carry;
+        self.flags
+            .trig_v_if((acc ^ val as u32) & (acc ^ binary) & sign != 0);
+
+        let result = if self.flags.d && self.decimal_mode {
+            let (mut result, mut borrow) = (0, 1 - carry as i32);
+            for shift in (0..digits).map(|digit| digit * 4) {
+                let mut diff =
+                    (acc >> shift & 0xf) as i32 - (val as u32 >> shift & 0xf) as i32 - borrow;
+                borrow = (diff < 0) as i32;
+                if diff < 0 {
+                    diff += 10;
+                }
+                result |= (diff as u32 & 0xf) << shift;
+            }
+            self.flags.trig_c_if(borrow == 0);
+            result
+        } else {
+            self.flags.trig_c_if(binary > mask);
+            binary & mask
+        };
+
+        self.set_acc(result as u16);
+        self.set_nz(result as u16, wide);
+    }
+
+    fn compare816(&mut self, reg: u16, val: u16, wide: bool) {
+        self.flags.trig_c_if(reg >= val);
+        self.set_nz(reg.wrapping_sub(val), wide);
+    }
+
+    fn shift816(&mut self, kind: Mnemonic, val: u16, wide: bool) -> u16 {
+        let msb = if wide { 15 } else { 7 };
+        let carry_in = self.flags.c as u16;
+        let (result, carry_out) = match kind {
+            Mnemonic::Asl => (val << 1, val >> msb & 1 != 0),
+            Mnemonic::Lsr => (val >> 1, val & 1 != 0),
+            Mnemonic::Rol => (val << 1 | carry_in, val >> msb & 1 != 0),
+            _ => (val >> 1 | carry_in << msb, val & 1 != 0),
+        };
+        let result = if wide { result } else { result & 0xff };
+
+        self.flags.trig_c_if(carry_out);
+        self.set_nz(result, wide);
+        result
+    }
+
+    fn branch816(&mut self, condition: bool, offset: u8, next: u16) -> u16 {
+        if !condition {
+            return next;
+        }
+        // Crossing a page only costs a cycle in emulation mode
+        let target = next.wrapping_add(offset as i8 as u16);
+        self.cyc += 1 + (self.emulation && cycles::page_crossed(next, target)) as u64;
+        target
+    }
+
+    pub fn interrupt816(&mut self, vector: u16, native_vector: u16, ret: u16, brk: bool) {
+        if self.emulation {
+            self.push816_word(ret);
+            self.push816(self.flags.pushed(brk));
+        } else {
+            // Native mode also saves the program bank and takes a cycle longer
+            self.push816(self.pbr);
+            self.push816_word(ret);
+            self.push816(self.status816());
+            self.cyc += 1;
+        }
+        self.flags.trig_i_if(true);
+        self.flags.trig_d_if(false);
+        self.pbr = 0x00;
+        self.pc = self.read_word(if self.emulation {
+            vector
+        } else {
+            native_vector
+        });
+    }
+
+    pub fn execute816(&mut self, pc: u16, opcode: u8) -> Outcome {
+        let program_bank = (self.pbr as u32) << 16;
+        let widths = self.widths();
+        let entry = optable::lookup(self.variant, opcode);
+        let op = entry.mnemonic;
+        let wide = widths.wide(op);
+        let (addr_mod, bytes) = entry.sized(wide);
+        let operands = &self.fetch_operands(program_bank, pc, bytes)[..bytes as usize - 1];
+        let addr_mod = &addr_mod;
+        let mut next = pc.wrapping_add(bytes as u16);
+        self.bus_read_long(program_bank | pc as u32);
+
+        self.cyc += entry.cycles as u64;
+        if wide {
+            // The extra byte costs a cycle, read-modify-write pays it twice
+            let rmw = matches!(
+                op,
+                Mnemonic::Asl
+                    | Mnemonic::Dec
+                    | Mnemonic::Inc
+                    | Mnemonic::Lsr
+                    | Mnemonic::Rol
+                    | Mnemonic::Ror
+                    | Mnemonic::Trb
+                    | Mnemonic::Tsb
+            );
+            self.cyc += 1 + (rmw && *addr_mod != AddrMod::Accumulator) as u64;
+        }
+        let direct = matches!(
+            addr_mod,
+            AddrMod::ZeroPage
+                | AddrMod::ZeroPageX
+                | AddrMod::ZeroPageY
+                | AddrMod::IndirectX
+                | AddrMod::IndirectY
+                | AddrMod::ZeroPageIndirect
+                | AddrMod::DirectIndirectLong
+                | AddrMod::DirectIndirectLongY
+        );
+        if direct && self.dp & 0xff != 0 {
+            self.cyc += 1;
+        }
+        if let Mnemonic::Adc
+        | Mnemonic::And
+        | Mnemonic::Bit
+        | Mnemonic::Cmp
+        | Mnemonic::Eor
+        | Mnemonic::Lda
+        | Mnemonic::Ldx
+        | Mnemonic::Ldy
+        | Mnemonic::Ora
+        | Mnemonic::Sbc = op
+        {
+            if let AddrMod::AbsoluteX | AddrMod::AbsoluteY | AddrMod::IndirectY = addr_mod {
+                // Wide index registers always pay for the high byte fix up
+                if let Some((_, crossed)) = self.resolve816(addr_mod, operands) {
+                    self.cyc += (crossed || widths.wide_index) as u64;
+                }
+            }
+        }
+
+        match op {
+            Mnemonic::Lda => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.set_acc(val);
+                    self.set_nz(val, wide);
+                }
+            }
+            Mnemonic::Ldx => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.set_index_x(val);
+                    self.set_nz(val, wide);
+                }
+            }
+            Mnemonic::Ldy => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.set_index_y(val);
+                    self.set_nz(val, wide);
+                }
+            }
+            Mnemonic::Sta => self.store816(addr_mod, operands, self.acc(), wide),
+            Mnemonic::Stx => self.store816(addr_mod, operands, self.x16(), wide),
+            Mnemonic::Sty => self.store816(addr_mod, operands, self.y16(), wide),
+            Mnemonic::Stz => self.store816(addr_mod, operands, 0x0000, wide),
+            Mnemonic::And | Mnemonic::Eor | Mnemonic::Ora => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    let val = match op {
+                        Mnemonic::And => self.acc() & val,
+                        Mnemonic::Eor => self.acc() ^ val,
+                        _ => self.acc() | val,
+                    };
+                    self.set_acc(val);
+                    self.set_nz(val, wide);
+                }
+            }
+            Mnemonic::Bit => {
+                // The immediate form only affects Z
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    let msb = if wide { 15 } else { 7 };
+                    self.flags.trig_z_if(self.acc() & val == 0);
+                    if *addr_mod != AddrMod::ImmediateWord && *addr_mod != AddrMod::Immediate {
+                        self.flags.trig_n_if(val >> msb & 1 != 0);
+                        self.flags.trig_v_if(val >> (msb - 1) & 1 != 0);
+                    }
+                }
+            }
+            Mnemonic::Trb | Mnemonic::Tsb => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.flags.trig_z_if(self.acc() & val == 0);
+                    let val = if op == Mnemonic::Trb {
+                        val & !self.acc()
+                    } else {
+                        val | self.acc()
+                    };
+                    self.store816(addr_mod, operands, val, wide);
+                }
+            }
+            Mnemonic::Adc => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.adc816(val, wide);
+                }
+            }
+            Mnemonic::Sbc => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.sbc816(val, wide);
+                }
+            }
+            Mnemonic::Cmp => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.compare816(self.acc(), val, wide);
+                }
+            }
+            Mnemonic::Cpx => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.compare816(self.x16(), val, wide);
+                }
+            }
+            Mnemonic::Cpy => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    self.compare816(self.y16(), val, wide);
+                }
+            }
+            Mnemonic::Inc | Mnemonic::Dec => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    let val = if op == Mnemonic::Inc {
+                        val.wrapping_add(1)
+                    } else {
+                        val.wrapping_sub(1)
+                    };
+                    let val = if wide { val } else { val & 0xff };
+                    self.store816(addr_mod, operands, val, wide);
+                    self.set_nz(val, wide);
+                }
+            }
+            Mnemonic::Asl | Mnemonic::Lsr | Mnemonic::Rol | Mnemonic::Ror => {
+                if let Some(val) = self.operand816(addr_mod, operands, wide) {
+                    let result = self.shift816(op, val, wide);
+                    self.store816(addr_mod, operands, result, wide);
+                }
+            }
+            Mnemonic::Inx | Mnemonic::Dex | Mnemonic::Iny | Mnemonic::Dey => {
+                let wide = widths.wide_index;
+                let val = match op {
+                    Mnemonic::Inx => self.x16().wrapping_add(1),
+                    Mnemonic::Dex => self.x16().wrapping_sub(1),
+                    Mnemonic::Iny => self.y16().wrapping_add(1),
+                    _ => self.y16().wrapping_sub(1),
+                };
+                let val = if wide { val } else { val & 0xff };
+                if let Mnemonic::Inx | Mnemonic::Dex = op {
+                    self.set_index_x(val);
+                } else {
+                    self.set_index_y(val);
+                }
+                self.set_nz(val, wide);
+            }
+
+            // Transfers take the width of the destination
+            Mnemonic::Tax | Mnemonic::Tyx => {
+                let val = if op == Mnemonic::Tax {
+                    self.c()
+                } else {
+                    self.y16()
+                };
+                self.set_index_x(val);
+                self.set_nz(self.x16(), widths.wide_index);
+            }
+            Mnemonic::Tay | Mnemonic::Txy => {
+                let val = if op == Mnemonic::Tay {
+                    self.c()
+                } else {
+                    self.x16()
+                };
+                self.set_index_y(val);
+                self.set_nz(self.y16(), widths.wide_index);
+            }
+            Mnemonic::Txa | Mnemonic::Tya => {
+                let val = if op == Mnemonic::Txa {
+                    self.x16()
+                } else {
+                    self.y16()
+                };
+                self.set_acc(val);
+                self.set_nz(self.acc(), widths.wide_a);
+            }
+            Mnemonic::Tsx => {
+                self.set_index_x(self.sp16());
+                self.set_nz(self.x16(), widths.wide_index);
+            }
+            Mnemonic::Txs => self.set_sp16(self.x16()),
+            Mnemonic::Tcs => self.set_sp16(self.c()),
+            Mnemonic::Tsc => {
+                self.set_c(self.sp16());
+                self.set_nz(self.c(), true);
+            }
+            Mnemonic::Tcd => {
+                self.dp = self.c();
+                self.set_nz(self.dp, true);
+            }
+            Mnemonic::Tdc => {
+                self.set_c(self.dp);
+                self.set_nz(self.dp, true);
+            }
+            Mnemonic::Xba => {
+                (self.a, self.ah) = (self.ah, self.a);
+                self.set_zn(self.a);
+            }
+            Mnemonic::Xce => {
+                let carry = self.flags.c;
+                self.flags.trig_c_if(self.emulation);
+                self.emulation = carry;
+                if self.emulation {
+                    self.flags.trig_m_if(true);
+                    self.flags.trig_x_if(true);
+                    self.xh = 0x00;
+                    self.yh = 0x00;
+                    self.sph = 0x01;
+                }
+            }
+
+            // Stack
+            Mnemonic::Pha => self.push_data(self.acc(), wide),
+            Mnemonic::Phx => self.push_data(self.x16(), wide),
+            Mnemonic::Phy => self.push_data(self.y16(), wide),
+            Mnemonic::Pla => {
+                let val = self.pull_data(wide);
+                self.set_acc(val);
+                self.set_nz(val, wide);
+            }
+            Mnemonic::Plx => {
+                let val = self.pull_data(wide);
+                self.set_index_x(val);
+                self.set_nz(val, wide);
+            }
+            Mnemonic::Ply => {
+                let val = self.pull_data(wide);
+                self.set_index_y(val);
+                self.set_nz(val, wide);
+            }
+            Mnemonic::Php => {
+                let status = if self.emulation {
+                    self.flags.pushed(true)
+                } else {
+                    self.status816()
+                };
+                self.push816(status);
+            }
+            Mnemonic::Plp => {
+                let (status, old) = (self.pull816(), self.flags.i);
+                self.set_status816(status);
+                self.poll_old_i(old);
+            }
+            Mnemonic::Phb => self.push816(self.dbr),
+            Mnemonic::Phk => self.push816(self.pbr),
+            Mnemonic::Phd => self.push816_word(self.dp),
+            Mnemonic::Plb => {
+                self.dbr = self.pull816();
+                self.set_zn(self.dbr);
+            }
+            Mnemonic::Pld => {
+                self.dp = self.pull816_word();
+                self.set_nz(self.dp, true);
+            }
+            Mnemonic::Pea => {
+                self.push816_word(u16::from_le_bytes([operands[0], operands[1]]));
+            }
+            Mnemonic::Pei => {
+                let val = self.direct_word(operands[0], 0);
+                self.push816_word(val);
+            }
+            Mnemonic::Per => {
+                let offset = u16::from_le_bytes([operands[0], operands[1]]);
+                self.push816_word(next.wrapping_add(offset));
+            }
+
+            // Jumps and calls
+            Mnemonic::Jmp | Mnemonic::Jml => {
+                let target = match addr_mod {
+                    AddrMod::Absolute => program_bank | operand_word(operands) as u32,
+                    _ => match self.resolve816(addr_mod, operands) {
+                        Some((target, _)) => target,
+                        None => program_bank | next as u32,
+                    },
+                };
+                self.pbr = (target >> 16) as u8;
+                next = target as u16;
+            }
+            Mnemonic::Jsr => {
+                // Stays inside the program bank, RTS adds one back
+                let target = match addr_mod {
+                    AddrMod::Absolute => operand_word(operands),
+                    _ => match self.resolve816(addr_mod, operands) {
+                        Some((target, _)) => target as u16,
+                        None => next,
+                    },
+                };
+                self.push816_word(next.wrapping_sub(1));
+                next = target;
+            }
+            Mnemonic::Jsl => {
+                self.push816(self.pbr);
+                self.push816_word(next.wrapping_sub(1));
+                self.pbr = operands[2];
+                next = operand_word(operands);
+            }
+            Mnemonic::Rts => {
+                next = self.pull816_word().wrapping_add(1);
+            }
+            Mnemonic::Rtl => {
+                next = self.pull816_word().wrapping_add(1);
+                self.pbr = self.pull816();
+            }
+            Mnemonic::Rti => {
+                let status = self.pull816();
+                self.set_status816(status);
+                next = self.pull816_word();
+                if !self.emulation {
+                    self.pbr = self.pull816();
+                    self.cyc += 1;
+                }
+            }
+
+            // Branches
+            Mnemonic::Bcc => next = self.branch816(!self.flags.c, operands[0], next),
+            Mnemonic::Bcs => next = self.branch816(self.flags.c, operands[0], next),
+            Mnemonic::Beq => next = self.branch816(self.flags.z, operands[0], next),
+            Mnemonic::Bne => next = self.branch816(!self.flags.z, operands[0], next),
+            Mnemonic::Bmi => next = self.branch816(self.flags.n, operands[0], next),
+            Mnemonic::Bpl => next = self.branch816(!self.flags.n, operands[0], next),
+            Mnemonic::Bvc => next = self.branch816(!self.flags.v, operands[0], next),
+            Mnemonic::Bvs => next = self.branch816(self.flags.v, operands[0], next),
+            Mnemonic::Bra => next = self.branch816(true, operands[0], next),
+            Mnemonic::Brl => {
+                next = next.wrapping_add(operand_word(operands));
+            }
+
+            // Status flags
+            Mnemonic::Clc => self.flags.trig_c_if(false),
+            Mnemonic::Cld => self.flags.trig_d_if(false),
+            Mnemonic::Cli => {
+                let old = self.flags.i;
+                self.flags.trig_i_if(false);
+                self.poll_old_i(old);
+            }
+            Mnemonic::Clv => self.flags.trig_v_if(false),
+            Mnemonic::Sec => self.flags.trig_c_if(true),
+            Mnemonic::Sed => self.flags.trig_d_if(true),
+            Mnemonic::Sei => {
+                let old = self.flags.i;
+                self.flags.trig_i_if(true);
+                self.poll_old_i(old);
+            }
+            Mnemonic::Rep => self.set_status816(self.status816() & !operands[0]),
+            Mnemonic::Sep => self.set_status816(self.status816() | operands[0]),
+
+            Mnemonic::Mvn | Mnemonic::Mvp => {
+                // Moves C + 1 bytes in one go, seven cycles per byte
+                let (dst, src) = (operands[0], operands[1]);
+                self.dbr = dst;
+                loop {
+                    let val = self.bus_read_long((src as u32) << 16 | self.x16() as u32);
+                    self.bus_write_long((dst as u32) << 16 | self.y16() as u32, val);
+                    let (x, y) = if op == Mnemonic::Mvn {
+                        (self.x16().wrapping_add(1), self.y16().wrapping_add(1))
+                    } else {
+                        (self.x16().wrapping_sub(1), self.y16().wrapping_sub(1))
+                    };
+                    let mask = if widths.wide_index { 0xffff } else { 0x00ff };
+                    self.set_index_x(x & mask);
+                    self.set_index_y(y & mask);
+                    self.set_c(self.c().wrapping_sub(1));
+                    if self.c() == 0xffff {
+                        break;
+                    }
+                    self.cyc += 7;
+                }
+            }
+
+            // System
+            Mnemonic::Brk | Mnemonic::Cop => {
+                if op == Mnemonic::Brk && self.brk_halts {
+                    return Outcome::Halted { pc, opcode };
+                }
+                // Both skip a signature byte
+                let (vector, native_vector) = if op == Mnemonic::Brk {
+                    (crate::cpu::IRQ_VECTOR, NATIVE_BRK_VECTOR)
+                } else {
+                    (COP_VECTOR, NATIVE_COP_VECTOR)
+                };
+                self.interrupt816(
+                    vector,
+                    native_vector,
+                    pc.wrapping_add(2),
+                    op == Mnemonic::Brk,
+                );
+                next = self.pc;
+            }
+            Mnemonic::Wai => {
+                self.waiting = true;
+                self.update_pc(next);
+                return Outcome::Waiting { pc, opcode };
+            }
+            Mnemonic::Stp => {
+                self.jammed = true;
+                return Outcome::Halted { pc, opcode };
+            }
+            Mnemonic::Nop | Mnemonic::Wdm => {}
+
+            // The 65C816 has no undocumented opcodes and no bit instructions
+            Mnemonic::Slo
+            | Mnemonic::Rla
+            | Mnemonic::Sre
+            | Mnemonic::Rra
+            | Mnemonic::Sax
+            | Mnemonic::Lax
+            | Mnemonic::Dcp
+            | Mnemonic::Isc
+            | Mnemonic::Anc
+            | Mnemonic::Alr
+            | Mnemonic::Arr
+            | Mnemonic::Axs
+            | Mnemonic::Las
+            | Mnemonic::Ane
+            | Mnemonic::Lxa
+            | Mnemonic::Sha
+            | Mnemonic::Shx
+            | Mnemonic::Shy
+            | Mnemonic::Tas
+            | Mnemonic::Jam
+            | Mnemonic::Rmb(_)
+            | Mnemonic::Smb(_)
+            | Mnemonic::Bbr(_)
+            | Mnemonic::Bbs(_) => return Outcome::IllegalOpcode { pc, opcode },
+        }
+
+        self.update_pc(next);
+
+        let fault = self.memory.take_fault();
+        match self.fault.take().or(fault) {
+            Some(addr) => Outcome::BusFault { pc, opcode, addr },
+            None => Outcome::Executed { pc, opcode },
+        }
+    }
+}
+
+fn operand_word(operands: &[u8]) -> u16 {
+    u16::from_le_bytes([operands[0], operands[1]])
+}
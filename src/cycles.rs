@@ -0,0 +1,8 @@
+// Cycles spent pushing PC and status and fetching the vector on IRQ/NMI
+pub const INTERRUPT: u8 = 7;
+// Reset goes through the same sequence with the pushes turned into reads
+pub const RESET: u8 = 7;
+
+pub fn page_crossed(a: u16, b: u16) -> bool {
+    a & 0xff00 != b & 0xff00
+}
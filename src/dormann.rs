@@ -0,0 +1,195 @@
+use crate::cpu::CPU;
+use crate::limits::RunLimits;
+use crate::memory::{Bus, Memory, MEMORY_SIZE};
+use crate::outcome::{Outcome, StopReason};
+use crate::variant::Variant;
+use std::fmt;
+use std::fs;
+
+// Klaus Dormann's 6502 functional and decimal tests, as assembled with their
+// default options. A failing check jumps onto itself, the functional test
+// also traps at FUNCTIONAL_SUCCESS once every test case passed, unless it
+// was assembled to trap somewhere else.
+pub const FUNCTIONAL_SUCCESS: u16 = 0x3469;
+// Number of the functional test case being run
+pub const TEST_CASE: u16 = 0x0200;
+// Operands of the last decimal addition or subtraction and its error flag
+pub const DECIMAL_N1: u16 = 0x0000;
+pub const DECIMAL_N2: u16 = 0x0001;
+pub const DECIMAL_ERROR: u16 = 0x000b;
+
+// The decimal test ends on STP, which the NMOS part would run as DCP abs,Y
+const STP: u8 = 0xdb;
+// Both tests finish well within this, used when the caller sets no limit
+const MAX_INSTRUCTIONS: u64 = 200_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Suite {
+    Functional,
+    Decimal,
+}
+
+impl Suite {
+    pub fn as_str(&self) -> String {
+        let suite = match self {
+            Suite::Functional => "Functional test",
+            Suite::Decimal => "Decimal test",
+        };
+        suite.to_string()
+    }
+
+    pub fn from_name(name: &str) -> Option<Suite> {
+        match name.to_ascii_lowercase().as_str() {
+            "functional" | "func" => Some(Suite::Functional),
+            "decimal" | "dec" => Some(Suite::Decimal),
+            _ => None,
+        }
+    }
+
+    // Where the binary is assembled to
+    pub fn origin(&self) -> u16 {
+        match self {
+            Suite::Functional => 0x0000,
+            Suite::Decimal => 0x0200,
+        }
+    }
+
+    pub fn start(&self) -> u16 {
+        match self {
+            Suite::Functional => 0x0400,
+            Suite::Decimal => 0x0200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Report {
+    pub suite: Suite,
+    pub passed: bool,
+    // How the run ended, its PC is where the test trapped or stopped
+    pub outcome: Outcome,
+    pub test_case: u8,
+    pub operands: (u8, u8),
+    pub cycles: u64,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pc = self.outcome.pc();
+        match (self.suite, self.passed, self.outcome) {
+            (_, true, _) => write!(
+                f,
+                "{} passed at 0x{:0>4x} after {} cycles",
+                self.suite.as_str(),
+                pc,
+                self.cycles
+            ),
+            (Suite::Functional, false, Outcome::Trapped { .. }) => write!(
+                f,
+                "{} failed in test case 0x{:0>2x}, trapped at 0x{:0>4x}",
+                self.suite.as_str(),
+                self.test_case,
+                pc
+            ),
+            (
+                Suite::Decimal,
+                false,
+                Outcome::Trapped { .. }
+                | Outcome::Stopped {
+                    reason: StopReason::Opcode,
+                    ..
+                },
+            ) => {
+                write!(
+                    f,
+                    "{} failed on 0x{:0>2x} and 0x{:0>2x}, stopped at 0x{:0>4x}",
+                    self.suite.as_str(),
+                    self.operands.0,
+                    self.operands.1,
+                    pc
+                )
+            }
+            (Suite::Functional, false, _) => write!(
+                f,
+                "{} did not finish in test case 0x{:0>2x}: {}",
+                self.suite.as_str(),
+                self.test_case,
+                self.outcome
+            ),
+            (Suite::Decimal, false, _) => {
+                write!(
+                    f,
+                    "{} did not finish: {}",
+                    self.suite.as_str(),
+                    self.outcome
+                )
+            }
+        }
+    }
+}
+
+// A full 64 KiB image always goes to $0000, anything smaller to the origin
+// the suite is assembled for
+pub fn load(suite: Suite, image: &[u8], variant: Variant) -> CPU<Memory> {
+    if image.len() >= MEMORY_SIZE {
+        CPU::with_variant(variant, &image[..MEMORY_SIZE], 0x0000)
+    } else {
+        CPU::with_variant(variant, image, suite.origin())
+    }
+}
+
+pub fn run<B: Bus>(
+    cpu: &mut CPU<B>,
+    suite: Suite,
+    success: Option<u16>,
+    limits: &RunLimits,
+) -> Report {
+    let mut limits = RunLimits {
+        detect_traps: true,
+        ..limits.clone()
+    };
+    if limits.max_instructions.is_none() && limits.max_cycles.is_none() {
+        limits.max_instructions = Some(MAX_INSTRUCTIONS);
+    }
+    if suite == Suite::Decimal {
+        limits.stop_on = Some(STP);
+    }
+
+    cpu.update_pc(suite.start());
+    let start_cyc = cpu.cyc;
+    let outcome = cpu.run(&limits);
+
+    let passed = match (suite, outcome) {
+        (Suite::Functional, Outcome::Trapped { pc, .. }) => {
+            pc == success.unwrap_or(FUNCTIONAL_SUCCESS)
+        }
+        (
+            Suite::Decimal,
+            Outcome::Stopped {
+                reason: StopReason::Opcode,
+                ..
+            },
+        ) => cpu.bus_peek(DECIMAL_ERROR) == 0,
+        _ => false,
+    };
+    Report {
+        suite,
+        passed,
+        outcome,
+        test_case: cpu.bus_peek(TEST_CASE),
+        operands: (cpu.bus_peek(DECIMAL_N1), cpu.bus_peek(DECIMAL_N2)),
+        cycles: cpu.cyc - start_cyc,
+    }
+}
+
+pub fn run_file(
+    filepath: &str,
+    suite: Suite,
+    variant: Variant,
+    success: Option<u16>,
+    limits: &RunLimits,
+) -> Result<Report, String> {
+    let image = fs::read(filepath).map_err(|err| format!("Can't read {}: {}", filepath, err))?;
+    let mut cpu = load(suite, &image, variant);
+    Ok(run(&mut cpu, suite, success, limits))
+}
@@ -0,0 +1,279 @@
+// Constant expressions in assembler operands and directives. A symbol that
+// isn't defined yet makes the whole expression unknown, which the first pass
+// accepts as a forward reference.
+//
+// From loosest to tightest binding:
+//   ||  &&  == = != <> < > <= >=  |  ^  &  << >>  + -  * / %
+// and the unary operators - + ~ ! along with < and > for the low and high
+// byte, which bind as tightly as the others so <label+1 is lo(label) + 1.
+
+pub type Resolver<'a> = dyn Fn(&str) -> Result<Option<i64>, String> + 'a;
+
+pub fn eval(text: &str, pc: i64, symbol: &Resolver) -> Result<Option<i64>, String> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        pc,
+        symbol,
+    };
+    let val = parser.expr()?;
+    if parser.rest().is_empty() {
+        Ok(val)
+    } else {
+        Err(format!("Unexpected '{}' in expression", parser.rest()))
+    }
+}
+
+// Longer operators first so << isn't taken for <
+const OPERATORS: [&str; 24] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "<>", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^",
+    "<", ">", "=", "~", "!", "(", ")",
+];
+
+// Binary operators by precedence level, loosest first
+const LEVELS: [&[&str]; 9] = [
+    &["||"],
+    &["&&"],
+    &["==", "=", "!=", "<>", "<", ">", "<=", ">="],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+struct Parser<'a, 'b> {
+    text: &'a str,
+    pos: usize,
+    pc: i64,
+    symbol: &'b Resolver<'b>,
+}
+
+impl Parser<'_, '_> {
+    fn rest(&self) -> &str {
+        self.text[self.pos..].trim()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek_op(&mut self) -> Option<&'static str> {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        OPERATORS.into_iter().find(|op| rest.starts_with(op))
+    }
+
+    fn eat(&mut self, ops: &[&str]) -> Option<&'static str> {
+        let op = self.peek_op().filter(|op| ops.contains(op))?;
+        self.pos += op.len();
+        Some(op)
+    }
+
+    fn expr(&mut self) -> Result<Option<i64>, String> {
+        self.binary(0)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Option<i64>, String> {
+        let operand = |parser: &mut Self| {
+            if level + 1 < LEVELS.len() {
+                parser.binary(level + 1)
+            } else {
+                parser.unary()
+            }
+        };
+        let mut lhs = operand(self)?;
+        while let Some(op) = self.eat(LEVELS[level]) {
+            let rhs = operand(self)?;
+            lhs = match (lhs, rhs) {
+                (Some(lhs), Some(rhs)) => Some(apply(op, lhs, rhs)?),
+                _ => None,
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Option<i64>, String> {
+        let Some(op) = self.eat(&["-", "+", "~", "!", "<", ">"]) else {
+            return self.primary();
+        };
+        let val = self.unary()?.map(|val| match op {
+            "-" => val.wrapping_neg(),
+            "~" => !val,
+            "!" => (val == 0) as i64,
+            "<" => val & 0xff,
+            ">" => (val >> 8) & 0xff,
+            _ => val,
+        });
+        Ok(val)
+    }
+
+    fn primary(&mut self) -> Result<Option<i64>, String> {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        let Some(first) = rest.chars().next() else {
+            return Err("Expected a value at the end of the expression".to_string());
+        };
+
+        match first {
+            '(' => {
+                self.pos += 1;
+                let val = self.expr()?;
+                if self.eat(&[")"]).is_none() {
+                    return Err("Missing ')' in expression".to_string());
+                }
+                Ok(val)
+            }
+            '*' => {
+                self.pos += 1;
+                Ok(Some(self.pc))
+            }
+            '\'' => self.char_literal().map(Some),
+            '$' | '%' | '0'..='9' => {
+                let len = rest[1..]
+                    .find(|ch: char| !ch.is_ascii_alphanumeric())
+                    .map_or(rest.len(), |len| len + 1);
+                let literal = &rest[..len];
+                self.pos += len;
+                parse_number(literal)
+                    .map(Some)
+                    .ok_or(format!("Invalid number '{}'", literal))
+            }
+            ch if ch.is_ascii_alphabetic() || ch == '_' || ch == '@' => {
+                let len = rest
+                    .find(|ch: char| !is_symbol_char(ch))
+                    .unwrap_or(rest.len());
+                let name = &rest[..len];
+                self.pos += len;
+                (self.symbol)(name)
+            }
+            _ => Err(format!("Unexpected '{}' in expression", rest)),
+        }
+    }
+
+    fn char_literal(&mut self) -> Result<i64, String> {
+        let rest = &self.text[self.pos + 1..];
+        let mut chars = rest.chars();
+        let (ch, len) = match chars.next() {
+            Some('\\') => {
+                let escaped = match chars.next() {
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('0') => '\0',
+                    Some(ch @ ('\\' | '\'' | '"')) => ch,
+                    _ => return Err(format!("Invalid escape in '{}", rest)),
+                };
+                (escaped, 2)
+            }
+            Some(ch) => (ch, ch.len_utf8()),
+            None => return Err("Unterminated character literal".to_string()),
+        };
+        if !rest[len..].starts_with('\'') {
+            return Err(format!("Unterminated character literal '{}", rest));
+        }
+        if !ch.is_ascii() {
+            return Err(format!("Non-ASCII character '{}'", ch));
+        }
+        self.pos += len + 2;
+        Ok(ch as i64)
+    }
+}
+
+fn apply(op: &str, lhs: i64, rhs: i64) -> Result<i64, String> {
+    let shift = || u32::try_from(rhs).map_err(|_| format!("Invalid shift by {}", rhs));
+    let val = match op {
+        "||" => (lhs != 0 || rhs != 0) as i64,
+        "&&" => (lhs != 0 && rhs != 0) as i64,
+        "==" | "=" => (lhs == rhs) as i64,
+        "!=" | "<>" => (lhs != rhs) as i64,
+        "<" => (lhs < rhs) as i64,
+        ">" => (lhs > rhs) as i64,
+        "<=" => (lhs <= rhs) as i64,
+        ">=" => (lhs >= rhs) as i64,
+        "|" => lhs | rhs,
+        "^" => lhs ^ rhs,
+        "&" => lhs & rhs,
+        "<<" => lhs.checked_shl(shift()?).unwrap_or(0),
+        ">>" => lhs.checked_shr(shift()?).unwrap_or(lhs >> 63),
+        "+" => lhs.wrapping_add(rhs),
+        "-" => lhs.wrapping_sub(rhs),
+        "*" => lhs.wrapping_mul(rhs),
+        "/" | "%" if rhs == 0 => return Err("Division by zero".to_string()),
+        "/" => lhs.wrapping_div(rhs),
+        "%" => lhs.wrapping_rem(rhs),
+        _ => return Err(format!("Unknown operator '{}'", op)),
+    };
+    Ok(val)
+}
+
+// @ marks labels local to a macro, expansions rename them to @name@n
+pub fn is_symbol_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '@'
+}
+
+pub fn is_identifier(text: &str) -> bool {
+    text.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_' || ch == '@')
+        && text.chars().all(is_symbol_char)
+}
+
+// $ff and 0xff are hex, %1010 and 0b1010 binary and anything else decimal
+pub fn parse_number(text: &str) -> Option<i64> {
+    if let Some(hex) = text.strip_prefix('$').or_else(|| text.strip_prefix("0x")) {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = text.strip_prefix('%').or_else(|| text.strip_prefix("0b")) {
+        i64::from_str_radix(bin, 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str) -> Result<Option<i64>, String> {
+        eval(text, 0x0600, &|name| match name {
+            "table" => Ok(Some(0x12fe)),
+            "later" => Ok(None),
+            _ => Err(format!("Undefined symbol '{}'", name)),
+        })
+    }
+
+    #[test]
+    fn precedence_and_byte_operators() {
+        assert_eq!(value("1 + 2 * 3"), Ok(Some(7)));
+        assert_eq!(value("(1 + 2) * 3"), Ok(Some(9)));
+        assert_eq!(value("1 << 4 | 1"), Ok(Some(17)));
+        assert_eq!(value("<table + 1"), Ok(Some(0xff)));
+        assert_eq!(value(">table"), Ok(Some(0x12)));
+        assert_eq!(value(">table == >(table + 1)"), Ok(Some(1)));
+        assert_eq!(value(">table == >(table + 2)"), Ok(Some(0)));
+        assert_eq!(value("* + 2"), Ok(Some(0x0602)));
+        assert_eq!(value("-1 & $ff"), Ok(Some(0xff)));
+    }
+
+    #[test]
+    fn numbers_and_characters() {
+        assert_eq!(value("$ff + 0x10 + %101 + 0b11 + 10"), Ok(Some(289)));
+        assert_eq!(value("'A' + '\\n'"), Ok(Some(75)));
+        assert_eq!(value("$fg"), Err("Invalid number '$fg'".to_string()));
+    }
+
+    #[test]
+    fn unknown_symbols_make_the_value_unknown() {
+        assert_eq!(value("later + 1"), Ok(None));
+        assert_eq!(value("later / 0"), Ok(None));
+        assert_eq!(value("1 / 0"), Err("Division by zero".to_string()));
+        assert_eq!(
+            value("nothing"),
+            Err("Undefined symbol 'nothing'".to_string())
+        );
+        assert_eq!(
+            value("(1 + 2"),
+            Err("Missing ')' in expression".to_string())
+        );
+    }
+}
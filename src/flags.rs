@@ -1,4 +1,18 @@
-#[derive(Debug)]
+// Bit positions in the status register. M and X only exist in 65C816 native
+// mode, where they take the places of the unused bit and B.
+pub const N: u8 = 0x80;
+pub const V: u8 = 0x40;
+pub const M: u8 = 0x20;
+pub const X: u8 = 0x10;
+pub const B: u8 = 0x10;
+pub const D: u8 = 0x08;
+pub const I: u8 = 0x04;
+pub const Z: u8 = 0x02;
+pub const C: u8 = 0x01;
+// Not connected to anything, always reads back as one
+pub const UNUSED: u8 = 0x20;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Flags {
     pub c: bool,
     pub z: bool,
@@ -7,6 +21,9 @@ pub struct Flags {
     pub b: bool,
     pub v: bool,
     pub n: bool,
+    // 65C816 native mode only, set means an 8 bit accumulator or index
+    pub m: bool,
+    pub x: bool,
 }
 
 impl Flags {
@@ -19,6 +36,8 @@ impl Flags {
             b: false,
             v: false,
             n: false,
+            m: false,
+            x: false,
         }
     }
 
@@ -33,6 +52,8 @@ impl Flags {
                 'B' => af.b = true,
                 'V' => af.v = true,
                 'N' => af.n = true,
+                'M' => af.m = true,
+                'X' => af.x = true,
                 _ => (),
             }
         }
@@ -48,62 +69,109 @@ impl Flags {
             b: true,
             v: true,
             n: true,
+            m: false,
+            x: false,
         }
     }
 
     pub fn trig_c_if(&mut self, condition: bool) {
-        if condition {
-            self.c = true;
-        } else {
-            self.c = false;
-        }
+        self.c = condition;
     }
 
     pub fn trig_z_if(&mut self, condition: bool) {
-        if condition {
-            self.z = true;
-        } else {
-            self.z = false;
-        }
+        self.z = condition;
     }
 
     pub fn trig_i_if(&mut self, condition: bool) {
-        if condition {
-            self.i = true;
-        } else {
-            self.i = false;
-        }
+        self.i = condition;
     }
 
     pub fn trig_d_if(&mut self, condition: bool) {
-        if condition {
-            self.d = true;
-        } else {
-            self.d = false;
-        }
+        self.d = condition;
     }
 
     pub fn trig_b_if(&mut self, condition: bool) {
-        if condition {
-            self.b = true;
-        } else {
-            self.b = false;
-        }
+        self.b = condition;
     }
 
     pub fn trig_v_if(&mut self, condition: bool) {
-        if condition {
-            self.v = true;
-        } else {
-            self.v = false;
-        }
+        self.v = condition;
     }
 
     pub fn trig_n_if(&mut self, condition: bool) {
-        if condition {
-            self.n = true;
+        self.n = condition;
+    }
+
+    pub fn trig_m_if(&mut self, condition: bool) {
+        self.m = condition;
+    }
+
+    pub fn trig_x_if(&mut self, condition: bool) {
+        self.x = condition;
+    }
+
+    // The copy pushed to the stack, B tells a PHP or BRK push (set) apart
+    // from an IRQ or NMI one (clear)
+    pub fn pushed(&self, brk: bool) -> u8 {
+        let status = u8::from(*self) & !B;
+        if brk {
+            status | B
         } else {
-            self.n = false;
+            status
+        }
+    }
+
+    // PLP and RTI, B and bit 5 only exist on the stack copy so the register
+    // keeps its own
+    pub fn pull(&mut self, status: u8) {
+        *self = Flags {
+            b: self.b,
+            m: self.m,
+            x: self.x,
+            ..Flags::from(status)
+        };
+    }
+
+    // One letter per bit from N down to C, upper case when set
+    pub fn as_str(&self) -> String {
+        let status = u8::from(*self);
+        "NV-BDIZC"
+            .chars()
+            .enumerate()
+            .map(|(idx, flag)| match flag {
+                '-' => flag,
+                _ if status & 0x80 >> idx != 0 => flag,
+                _ => flag.to_ascii_lowercase(),
+            })
+            .collect()
+    }
+}
+
+impl From<Flags> for u8 {
+    fn from(flags: Flags) -> u8 {
+        (flags.n as u8) << 7
+            | (flags.v as u8) << 6
+            | UNUSED
+            | (flags.b as u8) << 4
+            | (flags.d as u8) << 3
+            | (flags.i as u8) << 2
+            | (flags.z as u8) << 1
+            | flags.c as u8
+    }
+}
+
+impl From<u8> for Flags {
+    fn from(status: u8) -> Flags {
+        Flags {
+            c: status & C != 0,
+            z: status & Z != 0,
+            i: status & I != 0,
+            d: status & D != 0,
+            b: status & B != 0,
+            v: status & V != 0,
+            n: status & N != 0,
+            m: false,
+            x: false,
         }
     }
 }
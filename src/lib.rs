@@ -0,0 +1,21 @@
+pub mod addrmod;
+pub mod assembler;
+pub mod cpu;
+pub mod cpu816;
+pub mod cycles;
+pub mod dormann;
+pub mod expr;
+pub mod flags;
+pub mod instructions;
+pub mod json;
+pub mod limits;
+pub mod macros;
+pub mod memory;
+pub mod mnemonic;
+pub mod opcat;
+pub mod optable;
+pub mod outcome;
+pub mod port;
+pub mod shell;
+pub mod singlestep;
+pub mod variant;
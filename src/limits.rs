@@ -0,0 +1,25 @@
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+// Stop conditions for CPU::run, every limit is off unless set
+#[derive(Debug, Clone, Default)]
+pub struct RunLimits {
+    pub max_instructions: Option<u64>,
+    pub max_cycles: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub stop_at: Option<u16>,
+    // Stop before executing this opcode
+    pub stop_on: Option<u8>,
+    // Stop when PC leaves the loaded program image
+    pub stop_outside: bool,
+    // Stop on JMP * / BNE * style loops that jump back onto themselves
+    pub detect_traps: bool,
+    // Raised from outside (e.g. a Ctrl-C handler) to pause a running program
+    pub interrupt: Option<&'static AtomicBool>,
+}
+
+impl RunLimits {
+    pub fn new() -> RunLimits {
+        RunLimits::default()
+    }
+}
@@ -1,7 +1,7 @@
 #[macro_export]
 macro_rules! disassemble {
-    ($rom:expr) => {
-        assembler::disassemble($rom, true)
+    ($memory:expr, $start:expr, $end:expr, $variant:expr) => {
+        assembler::disassemble($memory, $start, $end, $variant, true)
     };
 }
 
@@ -1,56 +1,318 @@
-mod addrmod;
-mod assembler;
-mod cpu;
-mod flags;
-mod instructions;
-mod macros;
-mod memory;
-mod opcat;
-mod shell;
-
-use std::env;
+use emu6502::dormann::{self, Suite};
+use emu6502::limits::RunLimits;
+use emu6502::memory::UninitPolicy;
+use emu6502::variant::Variant;
+use emu6502::{assembler, cpu, shell, singlestep};
+use std::path::Path;
+use std::time::Duration;
+use std::{env, fs, process};
 
 fn main() {
-    // if let Some(filepath) = env::args().nth(1) {
-    //     let rom = assembler::read_rom(&filepath);
+    // emu6502 [--cpu <variant>] [-D NAME[=value]]... [-o <out>] [rom|source] [origin],
+    // EMU6502_CPU sets the default variant
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut variant_name = env::var("EMU6502_CPU").ok();
+    if let Some(idx) = args.iter().position(|arg| arg == "--cpu") {
+        args.remove(idx);
+        if idx < args.len() {
+            variant_name = Some(args.remove(idx));
+        }
+    }
+    let variant = match variant_name {
+        Some(name) => match Variant::from_name(&name) {
+            Some(variant) => variant,
+            None => {
+                eprintln!(
+                    "Unknown CPU variant {}, use {}",
+                    name,
+                    shell::variant_names()
+                );
+                return;
+            }
+        },
+        None => Variant::Nmos6502,
+    };
+
+    // Symbols for every source assembled, as -D NAME=value or -DNAME=value
+    let mut defines = vec![];
+    while let Some(idx) = args.iter().position(|arg| arg.starts_with("-D")) {
+        let mut define = args.remove(idx).split_off(2);
+        if define.is_empty() && idx < args.len() {
+            define = args.remove(idx);
+        }
+        match assembler::parse_define(&define) {
+            Ok(define) => defines.push(define),
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+    }
+    let mut output = None;
+    if let Some(idx) = args.iter().position(|arg| arg == "-o") {
+        args.remove(idx);
+        if idx < args.len() {
+            output = Some(args.remove(idx));
+        }
+    }
+
+    let origin = args
+        .get(1)
+        .and_then(|addr| shell::parse_addr(addr))
+        .unwrap_or(0x0000);
+    let source = args.first().filter(|filepath| {
+        Path::new(filepath)
+            .extension()
+            .is_some_and(|ext| ext == "s" || ext == "asm")
+    });
+    let (rom, origin) = match (source, args.first()) {
+        (Some(filepath), _) => {
+            match assembler::assemble_file(filepath, origin, variant, &defines) {
+                Ok(program) => (program.image, program.origin),
+                Err(errors) => {
+                    for err in errors {
+                        eprintln!("{}", err);
+                    }
+                    process::exit(1);
+                }
+            }
+        }
+        (None, Some(filepath)) => (assembler::read_rom(filepath), origin),
+        (None, None) => (vec![105, 255, 105, 10, 0x85, 10], origin),
+    };
+
+    // With -o the source is only built into a ROM, without starting the shell
+    if let Some(output) = output {
+        if source.is_none() {
+            eprintln!("-o needs a .s or .asm source to assemble");
+            process::exit(1);
+        }
+        if let Err(err) = fs::write(&output, &rom) {
+            eprintln!("Can't write {}: {}", output, err);
+            process::exit(1);
+        }
+        println!(
+            "Assembled {} bytes at 0x{:0>4x} into {}",
+            rom.len(),
+            origin,
+            output
+        );
+        return;
+    }
 
-    let rom = vec![105, 255, 105, 10, 0x85, 10];
     shell::welcome();
+    shell::install_interrupt_handler();
 
-    let mut cpu = cpu::CPU::new(&rom);
+    let mut cpu = cpu::CPU::with_variant(variant, &rom, origin);
+    println!("Emulating a {}", cpu.variant.as_str());
 
     loop {
         let mut input = String::new();
-        shell::prompt(&mut input, None);
+        if shell::prompt(&mut input, None) == 0 {
+            break;
+        }
 
-        let inp: Vec<&str> = input.split(" ").collect();
+        let inp: Vec<&str> = input.split(' ').collect();
 
         match shell::inp(&inp, 0) {
             "exit" => break,
             "disassemble" => {
-                let disasm = assembler::disassemble(&rom, true);
-                println!("{}", disasm);
+                let start = shell::parse_addr(shell::inp(&inp, 1)).unwrap_or(cpu.origin);
+                // A full 64K image ends past $FFFF, which a u16 can't hold
+                let end = shell::parse_addr(shell::inp(&inp, 2))
+                    .map(u32::from)
+                    .unwrap_or_else(|| (start as u32 + cpu.size as u32).min(0x10000));
+                println!("{}", cpu.disassemble(start, end));
             }
             "run" => {
-                cpu.run();
+                let mut limits = RunLimits {
+                    // Without a limit, stop when PC leaves the loaded program
+                    stop_outside: shell::inp(&inp, 1).is_empty(),
+                    detect_traps: true,
+                    interrupt: Some(&shell::INTERRUPTED),
+                    ..RunLimits::new()
+                };
+                // A limit that doesn't parse must not leave the run unbounded
+                let arg = shell::inp(&inp, 2);
+                let parsed = match shell::inp(&inp, 1) {
+                    "" | "free" => Some(()),
+                    "until" => shell::parse_addr(arg).map(|addr| limits.stop_at = Some(addr)),
+                    "for" => arg.parse().ok().map(|n| limits.max_instructions = Some(n)),
+                    "cycles" => arg.parse().ok().map(|n| limits.max_cycles = Some(n)),
+                    "time" => arg
+                        .parse()
+                        .ok()
+                        .map(|ms| limits.timeout = Some(Duration::from_millis(ms))),
+                    _ => None,
+                };
+                if parsed.is_none() {
+                    println!(
+                        "Invalid Command, use run [free|until <addr>|for <n>|cycles <n>|time <ms>]"
+                    );
+                    continue;
+                }
+
+                shell::set_running(true);
+                let outcome = cpu.run(&limits);
+                shell::set_running(false);
+                println!("{}", outcome);
             }
             "step" => {
-                cpu.step();
+                let outcome = cpu.step();
+                if !outcome.is_ok() {
+                    println!("{}", outcome);
+                }
             }
+            "dormann" => {
+                let suite = Suite::from_name(shell::inp(&inp, 1));
+                let filepath = shell::inp(&inp, 2);
+                let success = shell::inp(&inp, 3);
+                let valid = match suite {
+                    Some(Suite::Functional) => {
+                        success.is_empty() || shell::parse_addr(success).is_some()
+                    }
+                    // The decimal test checks its error flag once it stops
+                    Some(Suite::Decimal) => success.is_empty(),
+                    None => false,
+                };
+                let suite = match suite {
+                    Some(suite) if valid && !filepath.is_empty() => suite,
+                    _ => {
+                        println!(
+                            "Invalid Command, use dormann functional <file> [success addr] or dormann decimal <file>"
+                        );
+                        continue;
+                    }
+                };
+                let success = shell::parse_addr(success);
+                let limits = RunLimits {
+                    interrupt: Some(&shell::INTERRUPTED),
+                    ..RunLimits::new()
+                };
+
+                // Runs on a CPU of its own, the loaded program stays as it is
+                shell::set_running(true);
+                let report = dormann::run_file(filepath, suite, cpu.variant, success, &limits);
+                shell::set_running(false);
+                match report {
+                    Ok(report) => println!("{}", report),
+                    Err(err) => println!("{}", err),
+                }
+            }
+            "assemble" => {
+                let filepath = shell::inp(&inp, 1);
+                if filepath.is_empty() {
+                    println!("Invalid Command, use assemble <file> [origin]");
+                    continue;
+                }
+                let origin = shell::parse_addr(shell::inp(&inp, 2)).unwrap_or(cpu.origin);
+                match assembler::assemble_file(filepath, origin, cpu.variant, &defines) {
+                    Ok(program) => {
+                        cpu.load(&program.image, program.origin);
+                        cpu.update_pc(program.origin);
+                        println!(
+                            "Assembled {} bytes at 0x{:0>4x}",
+                            program.image.len(),
+                            program.origin
+                        );
+                    }
+                    Err(errors) => {
+                        for err in errors {
+                            println!("{}", err);
+                        }
+                    }
+                }
+            }
+            "singlestep" => {
+                let path = Path::new(shell::inp(&inp, 1));
+                if path.as_os_str().is_empty() {
+                    println!("Invalid Command, use singlestep <dir|file>");
+                    continue;
+                }
+                let reports = if path.is_dir() {
+                    singlestep::run_dir(path, cpu.variant)
+                } else {
+                    singlestep::run_file(path, cpu.variant).map(|report| vec![report])
+                };
+                match reports {
+                    Ok(reports) => {
+                        // Only opcodes with mismatches are worth listing
+                        for report in reports.iter().filter(|report| !report.passed()) {
+                            println!("{}", report);
+                        }
+                        let passed = reports.iter().filter(|report| report.passed()).count();
+                        let bus = reports.iter().filter(|report| report.bus > 0).count();
+                        println!(
+                            "{} of {} opcodes passed, {} with bus activity differences",
+                            passed,
+                            reports.len(),
+                            bus
+                        );
+                    }
+                    Err(err) => println!("{}", err),
+                }
+            }
+            "break" => match shell::parse_addr(shell::inp(&inp, 1)) {
+                Some(addr) => {
+                    if let Some(idx) = cpu.breakpoints.iter().position(|bp| *bp == addr) {
+                        cpu.breakpoints.remove(idx);
+                        println!("Breakpoint at 0x{:0>4x} removed", addr);
+                    } else {
+                        cpu.breakpoints.push(addr);
+                        println!("Breakpoint at 0x{:0>4x} added", addr);
+                    }
+                }
+                None => {
+                    for bp in &cpu.breakpoints {
+                        println!("0x{:0>4x}", bp);
+                    }
+                }
+            },
             "rest" => cpu.reset(),
+            "irq" | "nmi" => {
+                let asserted = match shell::inp(&inp, 1) {
+                    "on" => true,
+                    "off" => false,
+                    _ => {
+                        println!("Invalid Command, use irq|nmi on|off");
+                        continue;
+                    }
+                };
+                if shell::inp(&inp, 0) == "irq" {
+                    cpu.set_irq(asserted);
+                } else {
+                    cpu.set_nmi(asserted);
+                }
+            }
             "show" => match shell::inp(&inp, 1) {
                 "accu" => cpu.show_accu(),
                 "flags" => cpu.show_flags(),
                 "" => cpu.show_state(),
                 _ => println!("Invalid Command"),
             },
-            "settings" => {}
+            "settings" => match (shell::inp(&inp, 1), shell::inp(&inp, 2)) {
+                ("decimal", "on") => cpu.decimal_mode = true,
+                ("decimal", "off") => cpu.decimal_mode = false,
+                ("uninit", "zero") => cpu.memory.set_policy(UninitPolicy::Zero),
+                ("uninit", "random") => {
+                    let seed = shell::inp(&inp, 3).parse().unwrap_or(0x6502);
+                    cpu.memory.set_policy(UninitPolicy::Random(seed));
+                }
+                ("uninit", "fault") => cpu.memory.set_policy(UninitPolicy::Fault),
+                ("brk", "halt") => cpu.brk_halts = true,
+                ("brk", "irq") => cpu.brk_halts = false,
+                ("variant", name) if Variant::from_name(name).is_some() => {
+                    cpu.set_variant(Variant::from_name(name).unwrap());
+                    println!("Emulating a {}", cpu.variant.as_str());
+                }
+                _ => println!(
+                    "Invalid Command, use settings decimal on|off, settings uninit zero|random [seed]|fault, settings brk halt|irq or settings variant {}",
+                    shell::variant_names()
+                ),
+            },
             _ => {
                 println!("Invalid Command, try again");
             }
         }
     }
-    // } else {
-    //     println!("No argument provided");
-    // }
 }
@@ -1,18 +1,325 @@
-use std::collections::HashMap;
+use std::fmt;
 
-#[derive(Debug)]
-pub struct Memory(HashMap<u16, u8>);
+pub const MEMORY_SIZE: usize = 0x10000;
+pub const LONG_MEMORY_SIZE: usize = 0x1000000;
+
+pub trait Bus {
+    fn read(&mut self, addr: u16) -> u8;
+
+    fn write(&mut self, addr: u16, val: u8);
+
+    // Reads without side effects, used by the disassembler and the shell
+    fn peek(&self, addr: u16) -> u8;
+
+    // Writes that bypass write protection, used to load images into ROM
+    fn poke(&mut self, addr: u16, val: u8) {
+        self.write(addr, val);
+    }
+
+    fn load(&mut self, origin: u16, image: &[u8]) {
+        for (offset, val) in image.iter().enumerate() {
+            self.poke(origin.wrapping_add(offset as u16), *val);
+        }
+    }
+
+    // 24 bit accesses from a 65C816, buses without banks see every bank as
+    // a mirror of bank $00
+    fn read_long(&mut self, addr: u32) -> u8 {
+        self.read(addr as u16)
+    }
+
+    fn write_long(&mut self, addr: u32, val: u8) {
+        self.write(addr as u16, val);
+    }
+
+    fn peek_long(&self, addr: u32) -> u8 {
+        self.peek(addr as u16)
+    }
+
+    // Address of the last read that broke the bus rules, cleared when taken
+    fn take_fault(&mut self) -> Option<u16> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UninitPolicy {
+    // Never written cells read as zero
+    Zero,
+    // Never written cells hold seeded pseudo-random power-on garbage
+    Random(u64),
+    // Reading a never written cell faults and stops execution
+    Fault,
+}
+
+// Flat RAM covering the whole address space, 64 KiB unless it is built
+// with more banks for the 65C816
+pub struct Memory {
+    data: Vec<u8>,
+    written: Vec<bool>,
+    policy: UninitPolicy,
+    fault: Option<u16>,
+}
 
 impl Memory {
     pub fn new() -> Memory {
-        Memory(HashMap::new())
+        Memory::with_policy(UninitPolicy::Zero)
+    }
+
+    pub fn with_policy(policy: UninitPolicy) -> Memory {
+        Memory::with_size(MEMORY_SIZE, policy)
+    }
+
+    // All 256 banks of a 65C816 address space
+    pub fn long() -> Memory {
+        Memory::with_size(LONG_MEMORY_SIZE, UninitPolicy::Zero)
+    }
+
+    fn with_size(size: usize, policy: UninitPolicy) -> Memory {
+        let mut memory = Memory {
+            data: vec![0x00; size],
+            written: vec![false; size],
+            policy: UninitPolicy::Zero,
+            fault: None,
+        };
+        memory.set_policy(policy);
+        memory
+    }
+
+    pub fn policy(&self) -> UninitPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: UninitPolicy) {
+        // Only cells that were never written are affected
+        let mut state = match policy {
+            UninitPolicy::Random(seed) => seed | 1,
+            _ => 0,
+        };
+        for (val, written) in self.data.iter_mut().zip(&self.written) {
+            if *written {
+                continue;
+            }
+            *val = if state == 0 {
+                0x00
+            } else {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            };
+        }
+        self.policy = policy;
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Memory {
+        Memory::new()
+    }
+}
+
+impl Bus for Memory {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.read_long(addr as u32)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.write_long(addr as u32, val);
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.data[addr as usize]
+    }
+
+    fn read_long(&mut self, addr: u32) -> u8 {
+        // Sizes are powers of two, addresses past the end wrap around
+        let idx = addr as usize & (self.data.len() - 1);
+        if self.policy == UninitPolicy::Fault && !self.written[idx] {
+            self.fault = Some(addr as u16);
+        }
+        self.data[idx]
+    }
+
+    fn write_long(&mut self, addr: u32, val: u8) {
+        let idx = addr as usize & (self.data.len() - 1);
+        self.data[idx] = val;
+        self.written[idx] = true;
+    }
+
+    fn peek_long(&self, addr: u32) -> u8 {
+        self.data[addr as usize & (self.data.len() - 1)]
+    }
+
+    fn take_fault(&mut self) -> Option<u16> {
+        self.fault.take()
+    }
+}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Memory({} bytes, {:?})", self.data.len(), self.policy)
+    }
+}
+
+// Memory mapped hardware attached to a MappedBus. Addresses passed to a
+// device are offsets from the start of its region, after mirroring.
+pub trait Device {
+    fn read(&mut self, offset: u16) -> u8;
+
+    fn write(&mut self, offset: u16, val: u8);
+
+    fn peek(&self, _offset: u16) -> u8 {
+        0x00
+    }
+}
+
+pub enum RegionKind {
+    Ram(Vec<u8>),
+    Rom(Vec<u8>),
+    Device(Box<dyn Device>),
+}
+
+pub struct Region {
+    pub start: u16,
+    pub end: u16,
+    pub size: usize,
+    pub kind: RegionKind,
+}
+
+impl Region {
+    pub fn contains(&self, addr: u16) -> bool {
+        addr >= self.start && addr <= self.end
+    }
+
+    pub fn offset(&self, addr: u16) -> usize {
+        // Regions larger than their backing store mirror it
+        (addr - self.start) as usize % self.size
+    }
+}
+
+impl fmt::Debug for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            RegionKind::Ram(_) => "RAM",
+            RegionKind::Rom(_) => "ROM",
+            RegionKind::Device(_) => "Device",
+        };
+        write!(
+            f,
+            "{} ${:04x}-${:04x} ({} bytes)",
+            kind, self.start, self.end, self.size
+        )
+    }
+}
+
+// Address space built from RAM, ROM and device regions. Regions mapped later
+// take priority over earlier ones, unmapped addresses read back as open bus.
+#[derive(Debug, Default)]
+pub struct MappedBus {
+    pub regions: Vec<Region>,
+    pub open_bus: u8,
+}
+
+impl MappedBus {
+    pub fn new() -> MappedBus {
+        MappedBus {
+            regions: vec![],
+            open_bus: 0x00,
+        }
+    }
+
+    pub fn map_ram(&mut self, start: u16, end: u16, size: usize) -> &mut MappedBus {
+        self.map(start, end, size, RegionKind::Ram(vec![0x00; size]))
+    }
+
+    pub fn map_rom(&mut self, start: u16, end: u16, data: Vec<u8>) -> &mut MappedBus {
+        self.map(start, end, data.len(), RegionKind::Rom(data))
+    }
+
+    pub fn map_device(
+        &mut self,
+        start: u16,
+        end: u16,
+        size: usize,
+        device: Box<dyn Device>,
+    ) -> &mut MappedBus {
+        self.map(start, end, size, RegionKind::Device(device))
+    }
+
+    fn map(&mut self, start: u16, end: u16, size: usize, kind: RegionKind) -> &mut MappedBus {
+        assert!(start <= end, "Region ${:04x}-${:04x} is empty", start, end);
+        assert!(
+            size > 0,
+            "Region ${:04x}-${:04x} has no backing",
+            start,
+            end
+        );
+        self.regions.push(Region {
+            start,
+            end,
+            size,
+            kind,
+        });
+        self
+    }
+
+    fn region(&self, addr: u16) -> Option<usize> {
+        self.regions
+            .iter()
+            .rposition(|region| region.contains(addr))
+    }
+}
+
+impl Bus for MappedBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        if let Some(idx) = self.region(addr) {
+            let region = &mut self.regions[idx];
+            let offset = region.offset(addr);
+            self.open_bus = match &mut region.kind {
+                RegionKind::Ram(data) | RegionKind::Rom(data) => data[offset],
+                RegionKind::Device(device) => device.read(offset as u16),
+            };
+        }
+        self.open_bus
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.open_bus = val;
+        if let Some(idx) = self.region(addr) {
+            let region = &mut self.regions[idx];
+            let offset = region.offset(addr);
+            match &mut region.kind {
+                RegionKind::Ram(data) => data[offset] = val,
+                RegionKind::Rom(_) => (),
+                RegionKind::Device(device) => device.write(offset as u16, val),
+            }
+        }
     }
 
-    pub fn read(&self, addr: u16) -> u8 {
-        *self.0.get(&addr).unwrap()
+    fn peek(&self, addr: u16) -> u8 {
+        match self.region(addr) {
+            Some(idx) => {
+                let region = &self.regions[idx];
+                let offset = region.offset(addr);
+                match &region.kind {
+                    RegionKind::Ram(data) | RegionKind::Rom(data) => data[offset],
+                    RegionKind::Device(device) => device.peek(offset as u16),
+                }
+            }
+            None => self.open_bus,
+        }
     }
 
-    pub fn write(&mut self, addr: u16, val: u8) {
-        self.0.insert(addr, val);
+    fn poke(&mut self, addr: u16, val: u8) {
+        if let Some(idx) = self.region(addr) {
+            let region = &mut self.regions[idx];
+            let offset = region.offset(addr);
+            match &mut region.kind {
+                RegionKind::Ram(data) | RegionKind::Rom(data) => data[offset] = val,
+                RegionKind::Device(device) => device.write(offset as u16, val),
+            }
+        }
     }
 }
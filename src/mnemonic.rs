@@ -0,0 +1,531 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mnemonic {
+    Lda,
+    Ldx,
+    Ldy,
+    Sta,
+    Stx,
+    Sty,
+    Tax,
+    Tay,
+    Txa,
+    Tya,
+    Tsx,
+    Txs,
+    Pha,
+    Php,
+    Pla,
+    Plp,
+    And,
+    Eor,
+    Ora,
+    Bit,
+    Adc,
+    Sbc,
+    Cmp,
+    Cpx,
+    Cpy,
+    Inc,
+    Inx,
+    Iny,
+    Dec,
+    Dex,
+    Dey,
+    Asl,
+    Lsr,
+    Rol,
+    Ror,
+    Jmp,
+    Jsr,
+    Rts,
+    Bcc,
+    Bcs,
+    Beq,
+    Bmi,
+    Bne,
+    Bpl,
+    Bvc,
+    Bvs,
+    Clc,
+    Cld,
+    Cli,
+    Clv,
+    Sec,
+    Sed,
+    Sei,
+    Brk,
+    Nop,
+    Rti,
+    // Undocumented NMOS instructions
+    Slo,
+    Rla,
+    Sre,
+    Rra,
+    Sax,
+    Lax,
+    Dcp,
+    Isc,
+    Anc,
+    Alr,
+    Arr,
+    Axs,
+    Las,
+    Ane,
+    Lxa,
+    Sha,
+    Shx,
+    Shy,
+    Tas,
+    Jam,
+    // 65C02 instructions, the bit instructions carry their bit number
+    Bra,
+    Phx,
+    Phy,
+    Plx,
+    Ply,
+    Stz,
+    Trb,
+    Tsb,
+    Rmb(u8),
+    Smb(u8),
+    Bbr(u8),
+    Bbs(u8),
+    Wai,
+    Stp,
+    // 65C816 instructions
+    Brl,
+    Jml,
+    Jsl,
+    Rtl,
+    Cop,
+    Wdm,
+    Mvn,
+    Mvp,
+    Pea,
+    Pei,
+    Per,
+    Phb,
+    Phd,
+    Phk,
+    Plb,
+    Pld,
+    Rep,
+    Sep,
+    Tcd,
+    Tcs,
+    Tdc,
+    Tsc,
+    Txy,
+    Tyx,
+    Xba,
+    Xce,
+}
+
+impl Mnemonic {
+    // Every mnemonic except the 65C02 bit instructions, which take a bit number
+    pub const ALL: [Mnemonic; 112] = [
+        Mnemonic::Lda,
+        Mnemonic::Ldx,
+        Mnemonic::Ldy,
+        Mnemonic::Sta,
+        Mnemonic::Stx,
+        Mnemonic::Sty,
+        Mnemonic::Tax,
+        Mnemonic::Tay,
+        Mnemonic::Txa,
+        Mnemonic::Tya,
+        Mnemonic::Tsx,
+        Mnemonic::Txs,
+        Mnemonic::Pha,
+        Mnemonic::Php,
+        Mnemonic::Pla,
+        Mnemonic::Plp,
+        Mnemonic::And,
+        Mnemonic::Eor,
+        Mnemonic::Ora,
+        Mnemonic::Bit,
+        Mnemonic::Adc,
+        Mnemonic::Sbc,
+        Mnemonic::Cmp,
+        Mnemonic::Cpx,
+        Mnemonic::Cpy,
+        Mnemonic::Inc,
+        Mnemonic::Inx,
+        Mnemonic::Iny,
+        Mnemonic::Dec,
+        Mnemonic::Dex,
+        Mnemonic::Dey,
+        Mnemonic::Asl,
+        Mnemonic::Lsr,
+        Mnemonic::Rol,
+        Mnemonic::Ror,
+        Mnemonic::Jmp,
+        Mnemonic::Jsr,
+        Mnemonic::Rts,
+        Mnemonic::Bcc,
+        Mnemonic::Bcs,
+        Mnemonic::Beq,
+        Mnemonic::Bmi,
+        Mnemonic::Bne,
+        Mnemonic::Bpl,
+        Mnemonic::Bvc,
+        Mnemonic::Bvs,
+        Mnemonic::Clc,
+        Mnemonic::Cld,
+        Mnemonic::Cli,
+        Mnemonic::Clv,
+        Mnemonic::Sec,
+        Mnemonic::Sed,
+        Mnemonic::Sei,
+        Mnemonic::Brk,
+        Mnemonic::Nop,
+        Mnemonic::Rti,
+        Mnemonic::Slo,
+        Mnemonic::Rla,
+        Mnemonic::Sre,
+        Mnemonic::Rra,
+        Mnemonic::Sax,
+        Mnemonic::Lax,
+        Mnemonic::Dcp,
+        Mnemonic::Isc,
+        Mnemonic::Anc,
+        Mnemonic::Alr,
+        Mnemonic::Arr,
+        Mnemonic::Axs,
+        Mnemonic::Las,
+        Mnemonic::Ane,
+        Mnemonic::Lxa,
+        Mnemonic::Sha,
+        Mnemonic::Shx,
+        Mnemonic::Shy,
+        Mnemonic::Tas,
+        Mnemonic::Jam,
+        Mnemonic::Bra,
+        Mnemonic::Phx,
+        Mnemonic::Phy,
+        Mnemonic::Plx,
+        Mnemonic::Ply,
+        Mnemonic::Stz,
+        Mnemonic::Trb,
+        Mnemonic::Tsb,
+        Mnemonic::Wai,
+        Mnemonic::Stp,
+        Mnemonic::Brl,
+        Mnemonic::Jml,
+        Mnemonic::Jsl,
+        Mnemonic::Rtl,
+        Mnemonic::Cop,
+        Mnemonic::Wdm,
+        Mnemonic::Mvn,
+        Mnemonic::Mvp,
+        Mnemonic::Pea,
+        Mnemonic::Pei,
+        Mnemonic::Per,
+        Mnemonic::Phb,
+        Mnemonic::Phd,
+        Mnemonic::Phk,
+        Mnemonic::Plb,
+        Mnemonic::Pld,
+        Mnemonic::Rep,
+        Mnemonic::Sep,
+        Mnemonic::Tcd,
+        Mnemonic::Tcs,
+        Mnemonic::Tdc,
+        Mnemonic::Tsc,
+        Mnemonic::Txy,
+        Mnemonic::Tyx,
+        Mnemonic::Xba,
+        Mnemonic::Xce,
+    ];
+
+    pub fn as_str(&self) -> String {
+        match self {
+            Mnemonic::Rmb(bit) | Mnemonic::Smb(bit) | Mnemonic::Bbr(bit) | Mnemonic::Bbs(bit) => {
+                format!("{}{}", self.name(), bit)
+            }
+            _ => self.name().to_string(),
+        }
+    }
+
+    // The bit instructions without their bit number
+    fn name(&self) -> &'static str {
+        match self {
+            Mnemonic::Lda => "LDA",
+            Mnemonic::Ldx => "LDX",
+            Mnemonic::Ldy => "LDY",
+            Mnemonic::Sta => "STA",
+            Mnemonic::Stx => "STX",
+            Mnemonic::Sty => "STY",
+            Mnemonic::Tax => "TAX",
+            Mnemonic::Tay => "TAY",
+            Mnemonic::Txa => "TXA",
+            Mnemonic::Tya => "TYA",
+            Mnemonic::Tsx => "TSX",
+            Mnemonic::Txs => "TXS",
+            Mnemonic::Pha => "PHA",
+            Mnemonic::Php => "PHP",
+            Mnemonic::Pla => "PLA",
+            Mnemonic::Plp => "PLP",
+            Mnemonic::And => "AND",
+            Mnemonic::Eor => "EOR",
+            Mnemonic::Ora => "ORA",
+            Mnemonic::Bit => "BIT",
+            Mnemonic::Adc => "ADC",
+            Mnemonic::Sbc => "SBC",
+            Mnemonic::Cmp => "CMP",
+            Mnemonic::Cpx => "CPX",
+            Mnemonic::Cpy => "CPY",
+            Mnemonic::Inc => "INC",
+            Mnemonic::Inx => "INX",
+            Mnemonic::Iny => "INY",
+            Mnemonic::Dec => "DEC",
+            Mnemonic::Dex => "DEX",
+            Mnemonic::Dey => "DEY",
+            Mnemonic::Asl => "ASL",
+            Mnemonic::Lsr => "LSR",
+            Mnemonic::Rol => "ROL",
+            Mnemonic::Ror => "ROR",
+            Mnemonic::Jmp => "JMP",
+            Mnemonic::Jsr => "JSR",
+            Mnemonic::Rts => "RTS",
+            Mnemonic::Bcc => "BCC",
+            Mnemonic::Bcs => "BCS",
+            Mnemonic::Beq => "BEQ",
+            Mnemonic::Bmi => "BMI",
+            Mnemonic::Bne => "BNE",
+            Mnemonic::Bpl => "BPL",
+            Mnemonic::Bvc => "BVC",
+            Mnemonic::Bvs => "BVS",
+            Mnemonic::Clc => "CLC",
+            Mnemonic::Cld => "CLD",
+            Mnemonic::Cli => "CLI",
+            Mnemonic::Clv => "CLV",
+            Mnemonic::Sec => "SEC",
+            Mnemonic::Sed => "SED",
+            Mnemonic::Sei => "SEI",
+            Mnemonic::Brk => "BRK",
+            Mnemonic::Nop => "NOP",
+            Mnemonic::Rti => "RTI",
+            Mnemonic::Slo => "SLO",
+            Mnemonic::Rla => "RLA",
+            Mnemonic::Sre => "SRE",
+            Mnemonic::Rra => "RRA",
+            Mnemonic::Sax => "SAX",
+            Mnemonic::Lax => "LAX",
+            Mnemonic::Dcp => "DCP",
+            Mnemonic::Isc => "ISC",
+            Mnemonic::Anc => "ANC",
+            Mnemonic::Alr => "ALR",
+            Mnemonic::Arr => "ARR",
+            Mnemonic::Axs => "AXS",
+            Mnemonic::Las => "LAS",
+            Mnemonic::Ane => "ANE",
+            Mnemonic::Lxa => "LXA",
+            Mnemonic::Sha => "SHA",
+            Mnemonic::Shx => "SHX",
+            Mnemonic::Shy => "SHY",
+            Mnemonic::Tas => "TAS",
+            Mnemonic::Jam => "JAM",
+            Mnemonic::Bra => "BRA",
+            Mnemonic::Phx => "PHX",
+            Mnemonic::Phy => "PHY",
+            Mnemonic::Plx => "PLX",
+            Mnemonic::Ply => "PLY",
+            Mnemonic::Stz => "STZ",
+            Mnemonic::Trb => "TRB",
+            Mnemonic::Tsb => "TSB",
+            Mnemonic::Wai => "WAI",
+            Mnemonic::Stp => "STP",
+            Mnemonic::Brl => "BRL",
+            Mnemonic::Jml => "JML",
+            Mnemonic::Jsl => "JSL",
+            Mnemonic::Rtl => "RTL",
+            Mnemonic::Cop => "COP",
+            Mnemonic::Wdm => "WDM",
+            Mnemonic::Mvn => "MVN",
+            Mnemonic::Mvp => "MVP",
+            Mnemonic::Pea => "PEA",
+            Mnemonic::Pei => "PEI",
+            Mnemonic::Per => "PER",
+            Mnemonic::Phb => "PHB",
+            Mnemonic::Phd => "PHD",
+            Mnemonic::Phk => "PHK",
+            Mnemonic::Plb => "PLB",
+            Mnemonic::Pld => "PLD",
+            Mnemonic::Rep => "REP",
+            Mnemonic::Sep => "SEP",
+            Mnemonic::Tcd => "TCD",
+            Mnemonic::Tcs => "TCS",
+            Mnemonic::Tdc => "TDC",
+            Mnemonic::Tsc => "TSC",
+            Mnemonic::Txy => "TXY",
+            Mnemonic::Tyx => "TYX",
+            Mnemonic::Xba => "XBA",
+            Mnemonic::Xce => "XCE",
+            Mnemonic::Rmb(_) => "RMB",
+            Mnemonic::Smb(_) => "SMB",
+            Mnemonic::Bbr(_) => "BBR",
+            Mnemonic::Bbs(_) => "BBS",
+        }
+    }
+
+    pub fn desc(&self) -> String {
+        let desc = match self {
+            Mnemonic::Lda => "Load Accumulator",
+            Mnemonic::Ldx => "Load X Register",
+            Mnemonic::Ldy => "Load Y Register",
+            Mnemonic::Sta => "Store Accumulator",
+            Mnemonic::Stx => "Store X Register",
+            Mnemonic::Sty => "Store Y Register",
+            Mnemonic::Tax => "Transfer Accumulator to X",
+            Mnemonic::Tay => "Transfer Accumulator to Y",
+            Mnemonic::Txa => "Transfer X to Accumulator",
+            Mnemonic::Tya => "Transfer Y to Accumulator",
+            Mnemonic::Tsx => "Transfer Stack Pointer to X",
+            Mnemonic::Txs => "Transfer X to Stack Pointer",
+            Mnemonic::Pha => "Push Accumulator on Stack",
+            Mnemonic::Php => "Push Processor Status on Stack",
+            Mnemonic::Pla => "Pull Accumulator from Stack",
+            Mnemonic::Plp => "Pull Processor Status from Stack",
+            Mnemonic::And => "Logical AND",
+            Mnemonic::Eor => "Logical Exclusive OR",
+            Mnemonic::Ora => "Logical Inclusive OR",
+            Mnemonic::Bit => "Bit Test",
+            Mnemonic::Adc => "Add with Carry",
+            Mnemonic::Sbc => "Subtract with Carry",
+            Mnemonic::Cmp => "Compare Accumulator",
+            Mnemonic::Cpx => "Compare X Register",
+            Mnemonic::Cpy => "Compare Y Register",
+            Mnemonic::Inc => "Increment Memory Location",
+            Mnemonic::Inx => "Increment X Register",
+            Mnemonic::Iny => "Increment Y Register",
+            Mnemonic::Dec => "Decrement Memory Location",
+            Mnemonic::Dex => "Decrement X Register",
+            Mnemonic::Dey => "Decrement Y Register",
+            Mnemonic::Asl => "Arithmetic Shift Left",
+            Mnemonic::Lsr => "Logical Shift Right",
+            Mnemonic::Rol => "Rotate Left",
+            Mnemonic::Ror => "Rotate Right",
+            Mnemonic::Jmp => "Jump to New Location",
+            Mnemonic::Jsr => "Jump to New Location Saving Return Address",
+            Mnemonic::Rts => "Return from Subroutine",
+            Mnemonic::Bcc => "Branch if Carry Clear",
+            Mnemonic::Bcs => "Branch if Carry Set",
+            Mnemonic::Beq => "Branch if Zero Set",
+            Mnemonic::Bmi => "Branch if Negative Set",
+            Mnemonic::Bne => "Branch if Zero Clear",
+            Mnemonic::Bpl => "Branch if Negative Clear",
+            Mnemonic::Bvc => "Branch if Overflow Clear",
+            Mnemonic::Bvs => "Branch if Overflow Set",
+            Mnemonic::Clc => "Clear Carry Flag",
+            Mnemonic::Cld => "Clear Decimal Mode",
+            Mnemonic::Cli => "Clear Interrupt Disable Bit",
+            Mnemonic::Clv => "Clear Overflow Flag",
+            Mnemonic::Sec => "Set Carry Flag",
+            Mnemonic::Sed => "Set Decimal Mode",
+            Mnemonic::Sei => "Set Interrupt Disable",
+            Mnemonic::Brk => "Force an Interrupt",
+            Mnemonic::Nop => "No Operation",
+            Mnemonic::Rti => "Return from Interrupt",
+            Mnemonic::Slo => "ASL Memory then OR with Accumulator",
+            Mnemonic::Rla => "ROL Memory then AND with Accumulator",
+            Mnemonic::Sre => "LSR Memory then EOR with Accumulator",
+            Mnemonic::Rra => "ROR Memory then Add with Carry",
+            Mnemonic::Sax => "Store Accumulator AND X",
+            Mnemonic::Lax => "Load Accumulator and X",
+            Mnemonic::Dcp => "Decrement Memory then Compare",
+            Mnemonic::Isc => "Increment Memory then Subtract with Carry",
+            Mnemonic::Anc => "AND then Copy N to Carry",
+            Mnemonic::Alr => "AND then Logical Shift Right",
+            Mnemonic::Arr => "AND then Rotate Right",
+            Mnemonic::Axs => "X <- (A AND X) - Immediate",
+            Mnemonic::Las => "Load Memory AND Stack Pointer",
+            Mnemonic::Ane => "Accumulator <- (A OR Magic) AND X AND Immediate",
+            Mnemonic::Lxa => "Accumulator and X <- (A OR Magic) AND Immediate",
+            Mnemonic::Sha => "Store A AND X AND High Byte + 1",
+            Mnemonic::Shx => "Store X AND High Byte + 1",
+            Mnemonic::Shy => "Store Y AND High Byte + 1",
+            Mnemonic::Tas => "SP <- A AND X, Store SP AND High Byte + 1",
+            Mnemonic::Jam => "Halt the CPU",
+            Mnemonic::Bra => "Branch Always",
+            Mnemonic::Phx => "Push X on Stack",
+            Mnemonic::Phy => "Push Y on Stack",
+            Mnemonic::Plx => "Pull X from Stack",
+            Mnemonic::Ply => "Pull Y from Stack",
+            Mnemonic::Stz => "Store Zero",
+            Mnemonic::Trb => "Test and Reset Bits",
+            Mnemonic::Tsb => "Test and Set Bits",
+            Mnemonic::Wai => "Wait for Interrupt",
+            Mnemonic::Stp => "Stop the Processor",
+            Mnemonic::Brl => "Branch Always Long",
+            Mnemonic::Jml => "Jump Long",
+            Mnemonic::Jsl => "Jump to Subroutine Long",
+            Mnemonic::Rtl => "Return from Subroutine Long",
+            Mnemonic::Cop => "Co-Processor Interrupt",
+            Mnemonic::Wdm => "Reserved for Future Expansion",
+            Mnemonic::Mvn => "Block Move Next",
+            Mnemonic::Mvp => "Block Move Previous",
+            Mnemonic::Pea => "Push Effective Absolute Address",
+            Mnemonic::Pei => "Push Effective Indirect Address",
+            Mnemonic::Per => "Push Effective PC Relative Address",
+            Mnemonic::Phb => "Push Data Bank Register",
+            Mnemonic::Phd => "Push Direct Page Register",
+            Mnemonic::Phk => "Push Program Bank Register",
+            Mnemonic::Plb => "Pull Data Bank Register",
+            Mnemonic::Pld => "Pull Direct Page Register",
+            Mnemonic::Rep => "Reset Status Bits",
+            Mnemonic::Sep => "Set Status Bits",
+            Mnemonic::Tcd => "Transfer C Accumulator to Direct Page Register",
+            Mnemonic::Tcs => "Transfer C Accumulator to Stack Pointer",
+            Mnemonic::Tdc => "Transfer Direct Page Register to C Accumulator",
+            Mnemonic::Tsc => "Transfer Stack Pointer to C Accumulator",
+            Mnemonic::Txy => "Transfer Index X to Index Y",
+            Mnemonic::Tyx => "Transfer Index Y to Index X",
+            Mnemonic::Xba => "Exchange B and A Accumulator",
+            Mnemonic::Xce => "Exchange Carry and Emulation Bits",
+            Mnemonic::Rmb(bit) => return format!("Reset Memory Bit {}", bit),
+            Mnemonic::Smb(bit) => return format!("Set Memory Bit {}", bit),
+            Mnemonic::Bbr(bit) => return format!("Branch if Bit {} Reset", bit),
+            Mnemonic::Bbs(bit) => return format!("Branch if Bit {} Set", bit),
+        };
+        desc.to_string()
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = String;
+
+    // Case insensitive, the bit instructions take their bit number as a
+    // suffix like RMB3
+    fn from_str(name: &str) -> Result<Mnemonic, String> {
+        let name = name.trim();
+        if let Some(mnemonic) = Mnemonic::ALL
+            .into_iter()
+            .find(|m| m.name().eq_ignore_ascii_case(name))
+        {
+            return Ok(mnemonic);
+        }
+        let (prefix, bit) = match name.as_bytes() {
+            [prefix @ .., digit @ b'0'..=b'7'] if prefix.len() == 3 => (prefix, digit - b'0'),
+            _ => return Err(format!("Unknown mnemonic '{}'", name)),
+        };
+        [
+            Mnemonic::Rmb(bit),
+            Mnemonic::Smb(bit),
+            Mnemonic::Bbr(bit),
+            Mnemonic::Bbs(bit),
+        ]
+        .into_iter()
+        .find(|m| m.name().as_bytes().eq_ignore_ascii_case(prefix))
+        .ok_or_else(|| format!("Unknown mnemonic '{}'", name))
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OpCat {
     LoadStore,
     Register,
@@ -11,6 +11,7 @@ pub enum OpCat {
     Branch,
     StatusCtrl,
     SysFun,
+    Undocumented,
     Unimpl,
 }
 
@@ -28,6 +29,7 @@ impl OpCat {
             OpCat::Branch => "Branch",
             OpCat::StatusCtrl => "Status Contrl",
             OpCat::SysFun => "System Function",
+            OpCat::Undocumented => "Undocumented",
             OpCat::Unimpl => "Unimplemented",
         };
         op_cat.to_string()
@@ -0,0 +1,922 @@
+use crate::addrmod::AddrMod;
+use crate::flags::{B, C, D, I, M, N, V, X, Z};
+use crate::mnemonic::Mnemonic::{self, *};
+use crate::opcat::OpCat::{self, *};
+use crate::variant::Variant;
+
+// Everything the CPU needs to know about an opcode before fetching its
+// operands. aflags holds the status register bits it may change.
+#[derive(Debug, Clone, Copy)]
+pub struct Opcode {
+    pub mnemonic: Mnemonic,
+    pub addr_mod: AddrMod,
+    pub bytes: u8,
+    pub cycles: u8,
+    pub op_cat: OpCat,
+    pub aflags: u8,
+}
+
+impl Opcode {
+    // Addressing mode and length once the 65C816 register width is known,
+    // immediate operands grow a byte with a 16 bit register
+    pub fn sized(&self, wide: bool) -> (AddrMod, u8) {
+        if wide && self.addr_mod == AddrMod::Immediate {
+            (AddrMod::ImmediateWord, self.bytes + 1)
+        } else {
+            (self.addr_mod, self.bytes)
+        }
+    }
+}
+
+const fn op(
+    mnemonic: Mnemonic,
+    addr_mod: AddrMod,
+    op_cat: OpCat,
+    aflags: u8,
+    cycles: u8,
+) -> Opcode {
+    Opcode {
+        mnemonic,
+        addr_mod,
+        bytes: addr_mod.bytes() as u8,
+        cycles,
+        op_cat,
+        aflags,
+    }
+}
+
+pub fn lookup(variant: Variant, opcode: u8) -> &'static Opcode {
+    match variant {
+        Variant::Wdc65C02 => &CMOS[opcode as usize],
+        Variant::Wdc65C816 => &W65C816[opcode as usize],
+        _ => &NMOS[opcode as usize],
+    }
+}
+
+// The opcode byte for an instruction, the documented one wins where an
+// undocumented opcode does the same
+pub fn encode(variant: Variant, mnemonic: Mnemonic, addr_mod: AddrMod) -> Option<u8> {
+    let opcodes = (0..=0xff).filter(|opcode| {
+        let entry = lookup(variant, *opcode);
+        entry.mnemonic == mnemonic && entry.addr_mod == addr_mod
+    });
+    opcodes
+        .clone()
+        .find(|opcode| lookup(variant, *opcode).op_cat != Undocumented)
+        .or_else(|| opcodes.min())
+}
+
+// Every opcode of the NMOS 6502, undocumented ones included. Base cycles
+// leave out page crossing and taken branch penalties, the CPU adds those.
+pub static NMOS: [Opcode; 256] = [
+    op(Brk, AddrMod::Implied, StatusCtrl, B, 7),    // 0x00
+    op(Ora, AddrMod::IndirectX, Logical, N | Z, 6), // 0x01
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x02
+    op(Slo, AddrMod::IndirectX, Undocumented, N | Z | C, 8), // 0x03
+    op(Nop, AddrMod::ZeroPage, Undocumented, 0, 3), // 0x04
+    op(Ora, AddrMod::ZeroPage, Logical, N | Z, 3),  // 0x05
+    op(Asl, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x06
+    op(Slo, AddrMod::ZeroPage, Undocumented, N | Z | C, 5), // 0x07
+    op(Php, AddrMod::Implied, Stack, 0, 3),         // 0x08
+    op(Ora, AddrMod::Immediate, Logical, N | Z, 2), // 0x09
+    op(Asl, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x0A
+    op(Anc, AddrMod::Immediate, Undocumented, N | Z | C, 2), // 0x0B
+    op(Nop, AddrMod::Absolute, Undocumented, 0, 4), // 0x0C
+    op(Ora, AddrMod::Absolute, Logical, N | Z, 4),  // 0x0D
+    op(Asl, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x0E
+    op(Slo, AddrMod::Absolute, Undocumented, N | Z | C, 6), // 0x0F
+    op(Bpl, AddrMod::Relative, Branch, 0, 2),       // 0x10
+    op(Ora, AddrMod::IndirectY, Logical, N | Z, 5), // 0x11
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x12
+    op(Slo, AddrMod::IndirectY, Undocumented, N | Z | C, 8), // 0x13
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4), // 0x14
+    op(Ora, AddrMod::ZeroPageX, Logical, N | Z, 4), // 0x15
+    op(Asl, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x16
+    op(Slo, AddrMod::ZeroPageX, Undocumented, N | Z | C, 6), // 0x17
+    op(Clc, AddrMod::Implied, StatusCtrl, C, 2),    // 0x18
+    op(Ora, AddrMod::AbsoluteY, Logical, N | Z, 4), // 0x19
+    op(Nop, AddrMod::Implied, Undocumented, 0, 2),  // 0x1A
+    op(Slo, AddrMod::AbsoluteY, Undocumented, N | Z | C, 7), // 0x1B
+    op(Nop, AddrMod::AbsoluteX, Undocumented, 0, 4), // 0x1C
+    op(Ora, AddrMod::AbsoluteX, Logical, N | Z, 4), // 0x1D
+    op(Asl, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x1E
+    op(Slo, AddrMod::AbsoluteX, Undocumented, N | Z | C, 7), // 0x1F
+    op(Jsr, AddrMod::Absolute, JumpCall, 0, 6),     // 0x20
+    op(And, AddrMod::IndirectX, Logical, N | Z, 6), // 0x21
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x22
+    op(Rla, AddrMod::IndirectX, Undocumented, N | Z | C, 8), // 0x23
+    op(Bit, AddrMod::ZeroPage, Logical, N | V | Z, 3), // 0x24
+    op(And, AddrMod::ZeroPage, Logical, N | Z, 3),  // 0x25
+    op(Rol, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x26
+    op(Rla, AddrMod::ZeroPage, Undocumented, N | Z | C, 5), // 0x27
+    op(Plp, AddrMod::Implied, Stack, N | V | B | D | I | Z | C, 4), // 0x28
+    op(And, AddrMod::Immediate, Logical, N | Z, 2), // 0x29
+    op(Rol, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x2A
+    op(Anc, AddrMod::Immediate, Undocumented, N | Z | C, 2), // 0x2B
+    op(Bit, AddrMod::Absolute, Logical, N | V | Z, 4), // 0x2C
+    op(And, AddrMod::Absolute, Logical, N | Z, 4),  // 0x2D
+    op(Rol, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x2E
+    op(Rla, AddrMod::Absolute, Undocumented, N | Z | C, 6), // 0x2F
+    op(Bmi, AddrMod::Relative, Branch, 0, 2),       // 0x30
+    op(And, AddrMod::IndirectY, Logical, N | Z, 5), // 0x31
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x32
+    op(Rla, AddrMod::IndirectY, Undocumented, N | Z | C, 8), // 0x33
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4), // 0x34
+    op(And, AddrMod::ZeroPageX, Logical, N | Z, 4), // 0x35
+    op(Rol, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x36
+    op(Rla, AddrMod::ZeroPageX, Undocumented, N | Z | C, 6), // 0x37
+    op(Sec, AddrMod::Implied, StatusCtrl, C, 2),    // 0x38
+    op(And, AddrMod::AbsoluteY, Logical, N | Z, 4), // 0x39
+    op(Nop, AddrMod::Implied, Undocumented, 0, 2),  // 0x3A
+    op(Rla, AddrMod::AbsoluteY, Undocumented, N | Z | C, 7), // 0x3B
+    op(Nop, AddrMod::AbsoluteX, Undocumented, 0, 4), // 0x3C
+    op(And, AddrMod::AbsoluteX, Logical, N | Z, 4), // 0x3D
+    op(Rol, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x3E
+    op(Rla, AddrMod::AbsoluteX, Undocumented, N | Z | C, 7), // 0x3F
+    op(
+        Rti,
+        AddrMod::Implied,
+        StatusCtrl,
+        N | V | B | D | I | Z | C,
+        6,
+    ), // 0x40
+    op(Eor, AddrMod::IndirectX, Logical, N | Z, 6), // 0x41
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x42
+    op(Sre, AddrMod::IndirectX, Undocumented, N | Z | C, 8), // 0x43
+    op(Nop, AddrMod::ZeroPage, Undocumented, 0, 3), // 0x44
+    op(Eor, AddrMod::ZeroPage, Logical, N | Z, 3),  // 0x45
+    op(Lsr, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x46
+    op(Sre, AddrMod::ZeroPage, Undocumented, N | Z | C, 5), // 0x47
+    op(Pha, AddrMod::Implied, Stack, 0, 3),         // 0x48
+    op(Eor, AddrMod::Immediate, Logical, N | Z, 2), // 0x49
+    op(Lsr, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x4A
+    op(Alr, AddrMod::Immediate, Undocumented, N | Z | C, 2), // 0x4B
+    op(Jmp, AddrMod::Absolute, JumpCall, 0, 3),     // 0x4C
+    op(Eor, AddrMod::Absolute, Logical, N | Z, 4),  // 0x4D
+    op(Lsr, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x4E
+    op(Sre, AddrMod::Absolute, Undocumented, N | Z | C, 6), // 0x4F
+    op(Bvc, AddrMod::Relative, Branch, 0, 2),       // 0x50
+    op(Eor, AddrMod::IndirectY, Logical, N | Z, 5), // 0x51
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x52
+    op(Sre, AddrMod::IndirectY, Undocumented, N | Z | C, 8), // 0x53
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4), // 0x54
+    op(Eor, AddrMod::ZeroPageX, Logical, N | Z, 4), // 0x55
+    op(Lsr, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x56
+    op(Sre, AddrMod::ZeroPageX, Undocumented, N | Z | C, 6), // 0x57
+    op(Cli, AddrMod::Implied, StatusCtrl, I, 2),    // 0x58
+    op(Eor, AddrMod::AbsoluteY, Logical, N | Z, 4), // 0x59
+    op(Nop, AddrMod::Implied, Undocumented, 0, 2),  // 0x5A
+    op(Sre, AddrMod::AbsoluteY, Undocumented, N | Z | C, 7), // 0x5B
+    op(Nop, AddrMod::AbsoluteX, Undocumented, 0, 4), // 0x5C
+    op(Eor, AddrMod::AbsoluteX, Logical, N | Z, 4), // 0x5D
+    op(Lsr, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x5E
+    op(Sre, AddrMod::AbsoluteX, Undocumented, N | Z | C, 7), // 0x5F
+    op(Rts, AddrMod::Implied, JumpCall, 0, 6),      // 0x60
+    op(Adc, AddrMod::IndirectX, Arithmetic, N | V | Z | C, 6), // 0x61
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x62
+    op(Rra, AddrMod::IndirectX, Undocumented, N | V | Z | C, 8), // 0x63
+    op(Nop, AddrMod::ZeroPage, Undocumented, 0, 3), // 0x64
+    op(Adc, AddrMod::ZeroPage, Arithmetic, N | V | Z | C, 3), // 0x65
+    op(Ror, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x66
+    op(Rra, AddrMod::ZeroPage, Undocumented, N | V | Z | C, 5), // 0x67
+    op(Pla, AddrMod::Implied, Stack, N | Z, 4),     // 0x68
+    op(Adc, AddrMod::Immediate, Arithmetic, N | V | Z | C, 2), // 0x69
+    op(Ror, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x6A
+    op(Arr, AddrMod::Immediate, Undocumented, N | V | Z | C, 2), // 0x6B
+    op(Jmp, AddrMod::Indirect, JumpCall, 0, 5),     // 0x6C
+    op(Adc, AddrMod::Absolute, Arithmetic, N | V | Z | C, 4), // 0x6D
+    op(Ror, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x6E
+    op(Rra, AddrMod::Absolute, Undocumented, N | V | Z | C, 6), // 0x6F
+    op(Bvs, AddrMod::Relative, Branch, 0, 2),       // 0x70
+    op(Adc, AddrMod::IndirectY, Arithmetic, N | V | Z | C, 5), // 0x71
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x72
+    op(Rra, AddrMod::IndirectY, Undocumented, N | V | Z | C, 8), // 0x73
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4), // 0x74
+    op(Adc, AddrMod::ZeroPageX, Arithmetic, N | V | Z | C, 4), // 0x75
+    op(Ror, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x76
+    op(Rra, AddrMod::ZeroPageX, Undocumented, N | V | Z | C, 6), // 0x77
+    op(Sei, AddrMod::Implied, StatusCtrl, I, 2),    // 0x78
+    op(Adc, AddrMod::AbsoluteY, Arithmetic, N | V | Z | C, 4), // 0x79
+    op(Nop, AddrMod::Implied, Undocumented, 0, 2),  // 0x7A
+    op(Rra, AddrMod::AbsoluteY, Undocumented, N | V | Z | C, 7), // 0x7B
+    op(Nop, AddrMod::AbsoluteX, Undocumented, 0, 4), // 0x7C
+    op(Adc, AddrMod::AbsoluteX, Arithmetic, N | V | Z | C, 4), // 0x7D
+    op(Ror, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x7E
+    op(Rra, AddrMod::AbsoluteX, Undocumented, N | V | Z | C, 7), // 0x7F
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2), // 0x80
+    op(Sta, AddrMod::IndirectX, LoadStore, 0, 6),   // 0x81
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2), // 0x82
+    op(Sax, AddrMod::IndirectX, Undocumented, 0, 6), // 0x83
+    op(Sty, AddrMod::ZeroPage, LoadStore, 0, 3),    // 0x84
+    op(Sta, AddrMod::ZeroPage, LoadStore, 0, 3),    // 0x85
+    op(Stx, AddrMod::ZeroPage, LoadStore, 0, 3),    // 0x86
+    op(Sax, AddrMod::ZeroPage, Undocumented, 0, 3), // 0x87
+    op(Dey, AddrMod::Implied, IncDec, N | Z, 2),    // 0x88
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2), // 0x89
+    op(Txa, AddrMod::Implied, Register, N | Z, 2),  // 0x8A
+    op(Ane, AddrMod::Immediate, Undocumented, N | Z, 2), // 0x8B
+    op(Sty, AddrMod::Absolute, LoadStore, 0, 4),    // 0x8C
+    op(Sta, AddrMod::Absolute, LoadStore, 0, 4),    // 0x8D
+    op(Stx, AddrMod::Absolute, LoadStore, 0, 4),    // 0x8E
+    op(Sax, AddrMod::Absolute, Undocumented, 0, 4), // 0x8F
+    op(Bcc, AddrMod::Relative, Branch, 0, 2),       // 0x90
+    op(Sta, AddrMod::IndirectY, LoadStore, 0, 6),   // 0x91
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0x92
+    op(Sha, AddrMod::IndirectY, Undocumented, 0, 6), // 0x93
+    op(Sty, AddrMod::ZeroPageX, LoadStore, 0, 4),   // 0x94
+    op(Sta, AddrMod::ZeroPageX, LoadStore, 0, 4),   // 0x95
+    op(Stx, AddrMod::ZeroPageY, LoadStore, 0, 4),   // 0x96
+    op(Sax, AddrMod::ZeroPageY, Undocumented, 0, 4), // 0x97
+    op(Tya, AddrMod::Implied, Register, N | Z, 2),  // 0x98
+    op(Sta, AddrMod::AbsoluteY, LoadStore, 0, 5),   // 0x99
+    op(Txs, AddrMod::Implied, Stack, 0, 2),         // 0x9A
+    op(Tas, AddrMod::AbsoluteY, Undocumented, 0, 5), // 0x9B
+    op(Shy, AddrMod::AbsoluteX, Undocumented, 0, 5), // 0x9C
+    op(Sta, AddrMod::AbsoluteX, LoadStore, 0, 5),   // 0x9D
+    op(Shx, AddrMod::AbsoluteY, Undocumented, 0, 5), // 0x9E
+    op(Sha, AddrMod::AbsoluteY, Undocumented, 0, 5), // 0x9F
+    op(Ldy, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA0
+    op(Lda, AddrMod::IndirectX, LoadStore, N | Z, 6), // 0xA1
+    op(Ldx, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA2
+    op(Lax, AddrMod::IndirectX, Undocumented, N | Z, 6), // 0xA3
+    op(Ldy, AddrMod::ZeroPage, LoadStore, N | Z, 3), // 0xA4
+    op(Lda, AddrMod::ZeroPage, LoadStore, N | Z, 3), // 0xA5
+    op(Ldx, AddrMod::ZeroPage, LoadStore, N | Z, 3), // 0xA6
+    op(Lax, AddrMod::ZeroPage, Undocumented, N | Z, 3), // 0xA7
+    op(Tay, AddrMod::Implied, Register, N | Z, 2),  // 0xA8
+    op(Lda, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA9
+    op(Tax, AddrMod::Implied, Register, N | Z, 2),  // 0xAA
+    op(Lxa, AddrMod::Immediate, Undocumented, N | Z, 2), // 0xAB
+    op(Ldy, AddrMod::Absolute, LoadStore, N | Z, 4), // 0xAC
+    op(Lda, AddrMod::Absolute, LoadStore, N | Z, 4), // 0xAD
+    op(Ldx, AddrMod::Absolute, LoadStore, N | Z, 4), // 0xAE
+    op(Lax, AddrMod::Absolute, Undocumented, N | Z, 4), // 0xAF
+    op(Bcs, AddrMod::Relative, Branch, 0, 2),       // 0xB0
+    op(Lda, AddrMod::IndirectY, LoadStore, N | Z, 5), // 0xB1
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0xB2
+    op(Lax, AddrMod::IndirectY, Undocumented, N | Z, 5), // 0xB3
+    op(Ldy, AddrMod::ZeroPageX, LoadStore, N | Z, 4), // 0xB4
+    op(Lda, AddrMod::ZeroPageX, LoadStore, N | Z, 4), // 0xB5
+    op(Ldx, AddrMod::ZeroPageY, LoadStore, N | Z, 4), // 0xB6
+    op(Lax, AddrMod::ZeroPageY, Undocumented, N | Z, 4), // 0xB7
+    op(Clv, AddrMod::Implied, StatusCtrl, V, 2),    // 0xB8
+    op(Lda, AddrMod::AbsoluteY, LoadStore, N | Z, 4), // 0xB9
+    op(Tsx, AddrMod::Implied, Stack, N | Z, 2),     // 0xBA
+    op(Las, AddrMod::AbsoluteY, Undocumented, N | Z, 4), // 0xBB
+    op(Ldy, AddrMod::AbsoluteX, LoadStore, N | Z, 4), // 0xBC
+    op(Lda, AddrMod::AbsoluteX, LoadStore, N | Z, 4), // 0xBD
+    op(Ldx, AddrMod::AbsoluteY, LoadStore, N | Z, 4), // 0xBE
+    op(Lax, AddrMod::AbsoluteY, Undocumented, N | Z, 4), // 0xBF
+    op(Cpy, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xC0
+    op(Cmp, AddrMod::IndirectX, Arithmetic, N | Z | C, 6), // 0xC1
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2), // 0xC2
+    op(Dcp, AddrMod::IndirectX, Undocumented, N | Z | C, 8), // 0xC3
+    op(Cpy, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xC4
+    op(Cmp, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xC5
+    op(Dec, AddrMod::ZeroPage, IncDec, N | Z, 5),   // 0xC6
+    op(Dcp, AddrMod::ZeroPage, Undocumented, N | Z | C, 5), // 0xC7
+    op(Iny, AddrMod::Implied, IncDec, N | Z, 2),    // 0xC8
+    op(Cmp, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xC9
+    op(Dex, AddrMod::Implied, IncDec, N | Z, 2),    // 0xCA
+    op(Axs, AddrMod::Immediate, Undocumented, N | Z | C, 2), // 0xCB
+    op(Cpy, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xCC
+    op(Cmp, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xCD
+    op(Dec, AddrMod::Absolute, IncDec, N | Z, 6),   // 0xCE
+    op(Dcp, AddrMod::Absolute, Undocumented, N | Z | C, 6), // 0xCF
+    op(Bne, AddrMod::Relative, Branch, 0, 2),       // 0xD0
+    op(Cmp, AddrMod::IndirectY, Arithmetic, N | Z | C, 5), // 0xD1
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0xD2
+    op(Dcp, AddrMod::IndirectY, Undocumented, N | Z | C, 8), // 0xD3
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4), // 0xD4
+    op(Cmp, AddrMod::ZeroPageX, Arithmetic, N | Z | C, 4), // 0xD5
+    op(Dec, AddrMod::ZeroPageX, IncDec, N | Z, 6),  // 0xD6
+    op(Dcp, AddrMod::ZeroPageX, Undocumented, N | Z | C, 6), // 0xD7
+    op(Cld, AddrMod::Implied, StatusCtrl, D, 2),    // 0xD8
+    op(Cmp, AddrMod::AbsoluteY, Arithmetic, N | Z | C, 4), // 0xD9
+    op(Nop, AddrMod::Implied, Undocumented, 0, 2),  // 0xDA
+    op(Dcp, AddrMod::AbsoluteY, Undocumented, N | Z | C, 7), // 0xDB
+    op(Nop, AddrMod::AbsoluteX, Undocumented, 0, 4), // 0xDC
+    op(Cmp, AddrMod::AbsoluteX, Arithmetic, N | Z | C, 4), // 0xDD
+    op(Dec, AddrMod::AbsoluteX, IncDec, N | Z, 7),  // 0xDE
+    op(Dcp, AddrMod::AbsoluteX, Undocumented, N | Z | C, 7), // 0xDF
+    op(Cpx, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xE0
+    op(Sbc, AddrMod::IndirectX, Arithmetic, N | V | Z | C, 6), // 0xE1
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2), // 0xE2
+    op(Isc, AddrMod::IndirectX, Undocumented, N | V | Z | C, 8), // 0xE3
+    op(Cpx, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xE4
+    op(Sbc, AddrMod::ZeroPage, Arithmetic, N | V | Z | C, 3), // 0xE5
+    op(Inc, AddrMod::ZeroPage, IncDec, N | Z, 5),   // 0xE6
+    op(Isc, AddrMod::ZeroPage, Undocumented, N | V | Z | C, 5), // 0xE7
+    op(Inx, AddrMod::Implied, IncDec, N | Z, 2),    // 0xE8
+    op(Sbc, AddrMod::Immediate, Arithmetic, N | V | Z | C, 2), // 0xE9
+    op(Nop, AddrMod::Implied, StatusCtrl, 0, 2),    // 0xEA
+    op(Sbc, AddrMod::Immediate, Undocumented, N | V | Z | C, 2), // 0xEB
+    op(Cpx, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xEC
+    op(Sbc, AddrMod::Absolute, Arithmetic, N | V | Z | C, 4), // 0xED
+    op(Inc, AddrMod::Absolute, IncDec, N | Z, 6),   // 0xEE
+    op(Isc, AddrMod::Absolute, Undocumented, N | V | Z | C, 6), // 0xEF
+    op(Beq, AddrMod::Relative, Branch, 0, 2),       // 0xF0
+    op(Sbc, AddrMod::IndirectY, Arithmetic, N | V | Z | C, 5), // 0xF1
+    op(Jam, AddrMod::Implied, Undocumented, 0, 2),  // 0xF2
+    op(Isc, AddrMod::IndirectY, Undocumented, N | V | Z | C, 8), // 0xF3
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4), // 0xF4
+    op(Sbc, AddrMod::ZeroPageX, Arithmetic, N | V | Z | C, 4), // 0xF5
+    op(Inc, AddrMod::ZeroPageX, IncDec, N | Z, 6),  // 0xF6
+    op(Isc, AddrMod::ZeroPageX, Undocumented, N | V | Z | C, 6), // 0xF7
+    op(Sed, AddrMod::Implied, StatusCtrl, D, 2),    // 0xF8
+    op(Sbc, AddrMod::AbsoluteY, Arithmetic, N | V | Z | C, 4), // 0xF9
+    op(Nop, AddrMod::Implied, Undocumented, 0, 2),  // 0xFA
+    op(Isc, AddrMod::AbsoluteY, Undocumented, N | V | Z | C, 7), // 0xFB
+    op(Nop, AddrMod::AbsoluteX, Undocumented, 0, 4), // 0xFC
+    op(Sbc, AddrMod::AbsoluteX, Arithmetic, N | V | Z | C, 4), // 0xFD
+    op(Inc, AddrMod::AbsoluteX, IncDec, N | Z, 7),  // 0xFE
+    op(Isc, AddrMod::AbsoluteX, Undocumented, N | V | Z | C, 7), // 0xFF
+];
+
+// The WDC 65C02. Opcodes left undefined by WDC are NOPs that take as long
+// as their addressing mode suggests.
+pub static CMOS: [Opcode; 256] = [
+    op(Brk, AddrMod::Implied, StatusCtrl, B, 7),      // 0x00
+    op(Ora, AddrMod::IndirectX, Logical, N | Z, 6),   // 0x01
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2),  // 0x02
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x03
+    op(Tsb, AddrMod::ZeroPage, Logical, Z, 5),        // 0x04
+    op(Ora, AddrMod::ZeroPage, Logical, N | Z, 3),    // 0x05
+    op(Asl, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x06
+    op(Rmb(0), AddrMod::ZeroPage, Logical, 0, 5),     // 0x07
+    op(Php, AddrMod::Implied, Stack, 0, 3),           // 0x08
+    op(Ora, AddrMod::Immediate, Logical, N | Z, 2),   // 0x09
+    op(Asl, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x0A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x0B
+    op(Tsb, AddrMod::Absolute, Logical, Z, 6),        // 0x0C
+    op(Ora, AddrMod::Absolute, Logical, N | Z, 4),    // 0x0D
+    op(Asl, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x0E
+    op(Bbr(0), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x0F
+    op(Bpl, AddrMod::Relative, Branch, 0, 2),         // 0x10
+    op(Ora, AddrMod::IndirectY, Logical, N | Z, 5),   // 0x11
+    op(Ora, AddrMod::ZeroPageIndirect, Logical, N | Z, 5), // 0x12
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x13
+    op(Trb, AddrMod::ZeroPage, Logical, Z, 5),        // 0x14
+    op(Ora, AddrMod::ZeroPageX, Logical, N | Z, 4),   // 0x15
+    op(Asl, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x16
+    op(Rmb(1), AddrMod::ZeroPage, Logical, 0, 5),     // 0x17
+    op(Clc, AddrMod::Implied, StatusCtrl, C, 2),      // 0x18
+    op(Ora, AddrMod::AbsoluteY, Logical, N | Z, 4),   // 0x19
+    op(Inc, AddrMod::Accumulator, IncDec, N | Z, 2),  // 0x1A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x1B
+    op(Trb, AddrMod::Absolute, Logical, Z, 6),        // 0x1C
+    op(Ora, AddrMod::AbsoluteX, Logical, N | Z, 4),   // 0x1D
+    op(Asl, AddrMod::AbsoluteX, Shifts, N | Z | C, 6), // 0x1E
+    op(Bbr(1), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x1F
+    op(Jsr, AddrMod::Absolute, JumpCall, 0, 6),       // 0x20
+    op(And, AddrMod::IndirectX, Logical, N | Z, 6),   // 0x21
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2),  // 0x22
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x23
+    op(Bit, AddrMod::ZeroPage, Logical, N | V | Z, 3), // 0x24
+    op(And, AddrMod::ZeroPage, Logical, N | Z, 3),    // 0x25
+    op(Rol, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x26
+    op(Rmb(2), AddrMod::ZeroPage, Logical, 0, 5),     // 0x27
+    op(Plp, AddrMod::Implied, Stack, N | V | B | D | I | Z | C, 4), // 0x28
+    op(And, AddrMod::Immediate, Logical, N | Z, 2),   // 0x29
+    op(Rol, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x2A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x2B
+    op(Bit, AddrMod::Absolute, Logical, N | V | Z, 4), // 0x2C
+    op(And, AddrMod::Absolute, Logical, N | Z, 4),    // 0x2D
+    op(Rol, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x2E
+    op(Bbr(2), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x2F
+    op(Bmi, AddrMod::Relative, Branch, 0, 2),         // 0x30
+    op(And, AddrMod::IndirectY, Logical, N | Z, 5),   // 0x31
+    op(And, AddrMod::ZeroPageIndirect, Logical, N | Z, 5), // 0x32
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x33
+    op(Bit, AddrMod::ZeroPageX, Logical, N | V | Z, 4), // 0x34
+    op(And, AddrMod::ZeroPageX, Logical, N | Z, 4),   // 0x35
+    op(Rol, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x36
+    op(Rmb(3), AddrMod::ZeroPage, Logical, 0, 5),     // 0x37
+    op(Sec, AddrMod::Implied, StatusCtrl, C, 2),      // 0x38
+    op(And, AddrMod::AbsoluteY, Logical, N | Z, 4),   // 0x39
+    op(Dec, AddrMod::Accumulator, IncDec, N | Z, 2),  // 0x3A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x3B
+    op(Bit, AddrMod::AbsoluteX, Logical, N | V | Z, 4), // 0x3C
+    op(And, AddrMod::AbsoluteX, Logical, N | Z, 4),   // 0x3D
+    op(Rol, AddrMod::AbsoluteX, Shifts, N | Z | C, 6), // 0x3E
+    op(Bbr(3), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x3F
+    op(
+        Rti,
+        AddrMod::Implied,
+        StatusCtrl,
+        N | V | B | D | I | Z | C,
+        6,
+    ), // 0x40
+    op(Eor, AddrMod::IndirectX, Logical, N | Z, 6),   // 0x41
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2),  // 0x42
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x43
+    op(Nop, AddrMod::ZeroPage, Undocumented, 0, 3),   // 0x44
+    op(Eor, AddrMod::ZeroPage, Logical, N | Z, 3),    // 0x45
+    op(Lsr, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x46
+    op(Rmb(4), AddrMod::ZeroPage, Logical, 0, 5),     // 0x47
+    op(Pha, AddrMod::Implied, Stack, 0, 3),           // 0x48
+    op(Eor, AddrMod::Immediate, Logical, N | Z, 2),   // 0x49
+    op(Lsr, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x4A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x4B
+    op(Jmp, AddrMod::Absolute, JumpCall, 0, 3),       // 0x4C
+    op(Eor, AddrMod::Absolute, Logical, N | Z, 4),    // 0x4D
+    op(Lsr, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x4E
+    op(Bbr(4), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x4F
+    op(Bvc, AddrMod::Relative, Branch, 0, 2),         // 0x50
+    op(Eor, AddrMod::IndirectY, Logical, N | Z, 5),   // 0x51
+    op(Eor, AddrMod::ZeroPageIndirect, Logical, N | Z, 5), // 0x52
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x53
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4),  // 0x54
+    op(Eor, AddrMod::ZeroPageX, Logical, N | Z, 4),   // 0x55
+    op(Lsr, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x56
+    op(Rmb(5), AddrMod::ZeroPage, Logical, 0, 5),     // 0x57
+    op(Cli, AddrMod::Implied, StatusCtrl, I, 2),      // 0x58
+    op(Eor, AddrMod::AbsoluteY, Logical, N | Z, 4),   // 0x59
+    op(Phy, AddrMod::Implied, Stack, 0, 3),           // 0x5A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x5B
+    op(Nop, AddrMod::Absolute, Undocumented, 0, 8),   // 0x5C
+    op(Eor, AddrMod::AbsoluteX, Logical, N | Z, 4),   // 0x5D
+    op(Lsr, AddrMod::AbsoluteX, Shifts, N | Z | C, 6), // 0x5E
+    op(Bbr(5), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x5F
+    op(Rts, AddrMod::Implied, JumpCall, 0, 6),        // 0x60
+    op(Adc, AddrMod::IndirectX, Arithmetic, N | V | Z | C, 6), // 0x61
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2),  // 0x62
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x63
+    op(Stz, AddrMod::ZeroPage, LoadStore, 0, 3),      // 0x64
+    op(Adc, AddrMod::ZeroPage, Arithmetic, N | V | Z | C, 3), // 0x65
+    op(Ror, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x66
+    op(Rmb(6), AddrMod::ZeroPage, Logical, 0, 5),     // 0x67
+    op(Pla, AddrMod::Implied, Stack, N | Z, 4),       // 0x68
+    op(Adc, AddrMod::Immediate, Arithmetic, N | V | Z | C, 2), // 0x69
+    op(Ror, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x6A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x6B
+    op(Jmp, AddrMod::Indirect, JumpCall, 0, 6),       // 0x6C
+    op(Adc, AddrMod::Absolute, Arithmetic, N | V | Z | C, 4), // 0x6D
+    op(Ror, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x6E
+    op(Bbr(6), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x6F
+    op(Bvs, AddrMod::Relative, Branch, 0, 2),         // 0x70
+    op(Adc, AddrMod::IndirectY, Arithmetic, N | V | Z | C, 5), // 0x71
+    op(Adc, AddrMod::ZeroPageIndirect, Arithmetic, N | V | Z | C, 5), // 0x72
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x73
+    op(Stz, AddrMod::ZeroPageX, LoadStore, 0, 4),     // 0x74
+    op(Adc, AddrMod::ZeroPageX, Arithmetic, N | V | Z | C, 4), // 0x75
+    op(Ror, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x76
+    op(Rmb(7), AddrMod::ZeroPage, Logical, 0, 5),     // 0x77
+    op(Sei, AddrMod::Implied, StatusCtrl, I, 2),      // 0x78
+    op(Adc, AddrMod::AbsoluteY, Arithmetic, N | V | Z | C, 4), // 0x79
+    op(Ply, AddrMod::Implied, Stack, N | Z, 4),       // 0x7A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x7B
+    op(Jmp, AddrMod::AbsoluteIndirectX, JumpCall, 0, 6), // 0x7C
+    op(Adc, AddrMod::AbsoluteX, Arithmetic, N | V | Z | C, 4), // 0x7D
+    op(Ror, AddrMod::AbsoluteX, Shifts, N | Z | C, 6), // 0x7E
+    op(Bbr(7), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x7F
+    op(Bra, AddrMod::Relative, Branch, 0, 2),         // 0x80
+    op(Sta, AddrMod::IndirectX, LoadStore, 0, 6),     // 0x81
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2),  // 0x82
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x83
+    op(Sty, AddrMod::ZeroPage, LoadStore, 0, 3),      // 0x84
+    op(Sta, AddrMod::ZeroPage, LoadStore, 0, 3),      // 0x85
+    op(Stx, AddrMod::ZeroPage, LoadStore, 0, 3),      // 0x86
+    op(Smb(0), AddrMod::ZeroPage, Logical, 0, 5),     // 0x87
+    op(Dey, AddrMod::Implied, IncDec, N | Z, 2),      // 0x88
+    op(Bit, AddrMod::Immediate, Logical, N | V | Z, 2), // 0x89
+    op(Txa, AddrMod::Implied, Register, N | Z, 2),    // 0x8A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x8B
+    op(Sty, AddrMod::Absolute, LoadStore, 0, 4),      // 0x8C
+    op(Sta, AddrMod::Absolute, LoadStore, 0, 4),      // 0x8D
+    op(Stx, AddrMod::Absolute, LoadStore, 0, 4),      // 0x8E
+    op(Bbs(0), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x8F
+    op(Bcc, AddrMod::Relative, Branch, 0, 2),         // 0x90
+    op(Sta, AddrMod::IndirectY, LoadStore, 0, 6),     // 0x91
+    op(Sta, AddrMod::ZeroPageIndirect, LoadStore, 0, 5), // 0x92
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x93
+    op(Sty, AddrMod::ZeroPageX, LoadStore, 0, 4),     // 0x94
+    op(Sta, AddrMod::ZeroPageX, LoadStore, 0, 4),     // 0x95
+    op(Stx, AddrMod::ZeroPageY, LoadStore, 0, 4),     // 0x96
+    op(Smb(1), AddrMod::ZeroPage, Logical, 0, 5),     // 0x97
+    op(Tya, AddrMod::Implied, Register, N | Z, 2),    // 0x98
+    op(Sta, AddrMod::AbsoluteY, LoadStore, 0, 5),     // 0x99
+    op(Txs, AddrMod::Implied, Stack, 0, 2),           // 0x9A
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0x9B
+    op(Stz, AddrMod::Absolute, LoadStore, 0, 4),      // 0x9C
+    op(Sta, AddrMod::AbsoluteX, LoadStore, 0, 5),     // 0x9D
+    op(Stz, AddrMod::AbsoluteX, LoadStore, 0, 5),     // 0x9E
+    op(Bbs(1), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0x9F
+    op(Ldy, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA0
+    op(Lda, AddrMod::IndirectX, LoadStore, N | Z, 6), // 0xA1
+    op(Ldx, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA2
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xA3
+    op(Ldy, AddrMod::ZeroPage, LoadStore, N | Z, 3),  // 0xA4
+    op(Lda, AddrMod::ZeroPage, LoadStore, N | Z, 3),  // 0xA5
+    op(Ldx, AddrMod::ZeroPage, LoadStore, N | Z, 3),  // 0xA6
+    op(Smb(2), AddrMod::ZeroPage, Logical, 0, 5),     // 0xA7
+    op(Tay, AddrMod::Implied, Register, N | Z, 2),    // 0xA8
+    op(Lda, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA9
+    op(Tax, AddrMod::Implied, Register, N | Z, 2),    // 0xAA
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xAB
+    op(Ldy, AddrMod::Absolute, LoadStore, N | Z, 4),  // 0xAC
+    op(Lda, AddrMod::Absolute, LoadStore, N | Z, 4),  // 0xAD
+    op(Ldx, AddrMod::Absolute, LoadStore, N | Z, 4),  // 0xAE
+    op(Bbs(2), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0xAF
+    op(Bcs, AddrMod::Relative, Branch, 0, 2),         // 0xB0
+    op(Lda, AddrMod::IndirectY, LoadStore, N | Z, 5), // 0xB1
+    op(Lda, AddrMod::ZeroPageIndirect, LoadStore, N | Z, 5), // 0xB2
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xB3
+    op(Ldy, AddrMod::ZeroPageX, LoadStore, N | Z, 4), // 0xB4
+    op(Lda, AddrMod::ZeroPageX, LoadStore, N | Z, 4), // 0xB5
+    op(Ldx, AddrMod::ZeroPageY, LoadStore, N | Z, 4), // 0xB6
+    op(Smb(3), AddrMod::ZeroPage, Logical, 0, 5),     // 0xB7
+    op(Clv, AddrMod::Implied, StatusCtrl, V, 2),      // 0xB8
+    op(Lda, AddrMod::AbsoluteY, LoadStore, N | Z, 4), // 0xB9
+    op(Tsx, AddrMod::Implied, Stack, N | Z, 2),       // 0xBA
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xBB
+    op(Ldy, AddrMod::AbsoluteX, LoadStore, N | Z, 4), // 0xBC
+    op(Lda, AddrMod::AbsoluteX, LoadStore, N | Z, 4), // 0xBD
+    op(Ldx, AddrMod::AbsoluteY, LoadStore, N | Z, 4), // 0xBE
+    op(Bbs(3), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0xBF
+    op(Cpy, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xC0
+    op(Cmp, AddrMod::IndirectX, Arithmetic, N | Z | C, 6), // 0xC1
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2),  // 0xC2
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xC3
+    op(Cpy, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xC4
+    op(Cmp, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xC5
+    op(Dec, AddrMod::ZeroPage, IncDec, N | Z, 5),     // 0xC6
+    op(Smb(4), AddrMod::ZeroPage, Logical, 0, 5),     // 0xC7
+    op(Iny, AddrMod::Implied, IncDec, N | Z, 2),      // 0xC8
+    op(Cmp, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xC9
+    op(Dex, AddrMod::Implied, IncDec, N | Z, 2),      // 0xCA
+    op(Wai, AddrMod::Implied, SysFun, 0, 3),          // 0xCB
+    op(Cpy, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xCC
+    op(Cmp, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xCD
+    op(Dec, AddrMod::Absolute, IncDec, N | Z, 6),     // 0xCE
+    op(Bbs(4), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0xCF
+    op(Bne, AddrMod::Relative, Branch, 0, 2),         // 0xD0
+    op(Cmp, AddrMod::IndirectY, Arithmetic, N | Z | C, 5), // 0xD1
+    op(Cmp, AddrMod::ZeroPageIndirect, Arithmetic, N | Z | C, 5), // 0xD2
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xD3
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4),  // 0xD4
+    op(Cmp, AddrMod::ZeroPageX, Arithmetic, N | Z | C, 4), // 0xD5
+    op(Dec, AddrMod::ZeroPageX, IncDec, N | Z, 6),    // 0xD6
+    op(Smb(5), AddrMod::ZeroPage, Logical, 0, 5),     // 0xD7
+    op(Cld, AddrMod::Implied, StatusCtrl, D, 2),      // 0xD8
+    op(Cmp, AddrMod::AbsoluteY, Arithmetic, N | Z | C, 4), // 0xD9
+    op(Phx, AddrMod::Implied, Stack, 0, 3),           // 0xDA
+    op(Stp, AddrMod::Implied, SysFun, 0, 3),          // 0xDB
+    op(Nop, AddrMod::Absolute, Undocumented, 0, 4),   // 0xDC
+    op(Cmp, AddrMod::AbsoluteX, Arithmetic, N | Z | C, 4), // 0xDD
+    op(Dec, AddrMod::AbsoluteX, IncDec, N | Z, 7),    // 0xDE
+    op(Bbs(5), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0xDF
+    op(Cpx, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xE0
+    op(Sbc, AddrMod::IndirectX, Arithmetic, N | V | Z | C, 6), // 0xE1
+    op(Nop, AddrMod::Immediate, Undocumented, 0, 2),  // 0xE2
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xE3
+    op(Cpx, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xE4
+    op(Sbc, AddrMod::ZeroPage, Arithmetic, N | V | Z | C, 3), // 0xE5
+    op(Inc, AddrMod::ZeroPage, IncDec, N | Z, 5),     // 0xE6
+    op(Smb(6), AddrMod::ZeroPage, Logical, 0, 5),     // 0xE7
+    op(Inx, AddrMod::Implied, IncDec, N | Z, 2),      // 0xE8
+    op(Sbc, AddrMod::Immediate, Arithmetic, N | V | Z | C, 2), // 0xE9
+    op(Nop, AddrMod::Implied, StatusCtrl, 0, 2),      // 0xEA
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xEB
+    op(Cpx, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xEC
+    op(Sbc, AddrMod::Absolute, Arithmetic, N | V | Z | C, 4), // 0xED
+    op(Inc, AddrMod::Absolute, IncDec, N | Z, 6),     // 0xEE
+    op(Bbs(6), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0xEF
+    op(Beq, AddrMod::Relative, Branch, 0, 2),         // 0xF0
+    op(Sbc, AddrMod::IndirectY, Arithmetic, N | V | Z | C, 5), // 0xF1
+    op(Sbc, AddrMod::ZeroPageIndirect, Arithmetic, N | V | Z | C, 5), // 0xF2
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xF3
+    op(Nop, AddrMod::ZeroPageX, Undocumented, 0, 4),  // 0xF4
+    op(Sbc, AddrMod::ZeroPageX, Arithmetic, N | V | Z | C, 4), // 0xF5
+    op(Inc, AddrMod::ZeroPageX, IncDec, N | Z, 6),    // 0xF6
+    op(Smb(7), AddrMod::ZeroPage, Logical, 0, 5),     // 0xF7
+    op(Sed, AddrMod::Implied, StatusCtrl, D, 2),      // 0xF8
+    op(Sbc, AddrMod::AbsoluteY, Arithmetic, N | V | Z | C, 4), // 0xF9
+    op(Plx, AddrMod::Implied, Stack, N | Z, 4),       // 0xFA
+    op(Nop, AddrMod::Implied, Undocumented, 0, 1),    // 0xFB
+    op(Nop, AddrMod::Absolute, Undocumented, 0, 4),   // 0xFC
+    op(Sbc, AddrMod::AbsoluteX, Arithmetic, N | V | Z | C, 4), // 0xFD
+    op(Inc, AddrMod::AbsoluteX, IncDec, N | Z, 7),    // 0xFE
+    op(Bbs(7), AddrMod::ZeroPageRelative, Branch, 0, 5), // 0xFF
+];
+
+// The 65C816 with 8 bit registers. Immediate operands of accumulator and
+// index instructions grow a byte when M or X are clear, as do their cycles.
+pub static W65C816: [Opcode; 256] = [
+    op(Brk, AddrMod::Implied, StatusCtrl, B, 7),    // 0x00
+    op(Ora, AddrMod::IndirectX, Logical, N | Z, 6), // 0x01
+    op(Cop, AddrMod::Immediate, SysFun, D | I, 7),  // 0x02
+    op(Ora, AddrMod::StackRelative, Logical, N | Z, 4), // 0x03
+    op(Tsb, AddrMod::ZeroPage, Logical, Z, 5),      // 0x04
+    op(Ora, AddrMod::ZeroPage, Logical, N | Z, 3),  // 0x05
+    op(Asl, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x06
+    op(Ora, AddrMod::DirectIndirectLong, Logical, N | Z, 6), // 0x07
+    op(Php, AddrMod::Implied, Stack, 0, 3),         // 0x08
+    op(Ora, AddrMod::Immediate, Logical, N | Z, 2), // 0x09
+    op(Asl, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x0A
+    op(Phd, AddrMod::Implied, Stack, 0, 4),         // 0x0B
+    op(Tsb, AddrMod::Absolute, Logical, Z, 6),      // 0x0C
+    op(Ora, AddrMod::Absolute, Logical, N | Z, 4),  // 0x0D
+    op(Asl, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x0E
+    op(Ora, AddrMod::AbsoluteLong, Logical, N | Z, 5), // 0x0F
+    op(Bpl, AddrMod::Relative, Branch, 0, 2),       // 0x10
+    op(Ora, AddrMod::IndirectY, Logical, N | Z, 5), // 0x11
+    op(Ora, AddrMod::ZeroPageIndirect, Logical, N | Z, 5), // 0x12
+    op(Ora, AddrMod::StackRelativeIndirectY, Logical, N | Z, 7), // 0x13
+    op(Trb, AddrMod::ZeroPage, Logical, Z, 5),      // 0x14
+    op(Ora, AddrMod::ZeroPageX, Logical, N | Z, 4), // 0x15
+    op(Asl, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x16
+    op(Ora, AddrMod::DirectIndirectLongY, Logical, N | Z, 6), // 0x17
+    op(Clc, AddrMod::Implied, StatusCtrl, C, 2),    // 0x18
+    op(Ora, AddrMod::AbsoluteY, Logical, N | Z, 4), // 0x19
+    op(Inc, AddrMod::Accumulator, IncDec, N | Z, 2), // 0x1A
+    op(Tcs, AddrMod::Implied, Register, 0, 2),      // 0x1B
+    op(Trb, AddrMod::Absolute, Logical, Z, 6),      // 0x1C
+    op(Ora, AddrMod::AbsoluteX, Logical, N | Z, 4), // 0x1D
+    op(Asl, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x1E
+    op(Ora, AddrMod::AbsoluteLongX, Logical, N | Z, 5), // 0x1F
+    op(Jsr, AddrMod::Absolute, JumpCall, 0, 6),     // 0x20
+    op(And, AddrMod::IndirectX, Logical, N | Z, 6), // 0x21
+    op(Jsl, AddrMod::AbsoluteLong, JumpCall, 0, 8), // 0x22
+    op(And, AddrMod::StackRelative, Logical, N | Z, 4), // 0x23
+    op(Bit, AddrMod::ZeroPage, Logical, N | V | Z, 3), // 0x24
+    op(And, AddrMod::ZeroPage, Logical, N | Z, 3),  // 0x25
+    op(Rol, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x26
+    op(And, AddrMod::DirectIndirectLong, Logical, N | Z, 6), // 0x27
+    op(Plp, AddrMod::Implied, Stack, N | V | B | D | I | Z | C, 4), // 0x28
+    op(And, AddrMod::Immediate, Logical, N | Z, 2), // 0x29
+    op(Rol, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x2A
+    op(Pld, AddrMod::Implied, Stack, N | Z, 5),     // 0x2B
+    op(Bit, AddrMod::Absolute, Logical, N | V | Z, 4), // 0x2C
+    op(And, AddrMod::Absolute, Logical, N | Z, 4),  // 0x2D
+    op(Rol, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x2E
+    op(And, AddrMod::AbsoluteLong, Logical, N | Z, 5), // 0x2F
+    op(Bmi, AddrMod::Relative, Branch, 0, 2),       // 0x30
+    op(And, AddrMod::IndirectY, Logical, N | Z, 5), // 0x31
+    op(And, AddrMod::ZeroPageIndirect, Logical, N | Z, 5), // 0x32
+    op(And, AddrMod::StackRelativeIndirectY, Logical, N | Z, 7), // 0x33
+    op(Bit, AddrMod::ZeroPageX, Logical, N | V | Z, 4), // 0x34
+    op(And, AddrMod::ZeroPageX, Logical, N | Z, 4), // 0x35
+    op(Rol, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x36
+    op(And, AddrMod::DirectIndirectLongY, Logical, N | Z, 6), // 0x37
+    op(Sec, AddrMod::Implied, StatusCtrl, C, 2),    // 0x38
+    op(And, AddrMod::AbsoluteY, Logical, N | Z, 4), // 0x39
+    op(Dec, AddrMod::Accumulator, IncDec, N | Z, 2), // 0x3A
+    op(Tsc, AddrMod::Implied, Register, N | Z, 2),  // 0x3B
+    op(Bit, AddrMod::AbsoluteX, Logical, N | V | Z, 4), // 0x3C
+    op(And, AddrMod::AbsoluteX, Logical, N | Z, 4), // 0x3D
+    op(Rol, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x3E
+    op(And, AddrMod::AbsoluteLongX, Logical, N | Z, 5), // 0x3F
+    op(
+        Rti,
+        AddrMod::Implied,
+        StatusCtrl,
+        N | V | B | D | I | Z | C,
+        6,
+    ), // 0x40
+    op(Eor, AddrMod::IndirectX, Logical, N | Z, 6), // 0x41
+    op(Wdm, AddrMod::Immediate, SysFun, 0, 2),      // 0x42
+    op(Eor, AddrMod::StackRelative, Logical, N | Z, 4), // 0x43
+    op(Mvp, AddrMod::BlockMove, LoadStore, 0, 7),   // 0x44
+    op(Eor, AddrMod::ZeroPage, Logical, N | Z, 3),  // 0x45
+    op(Lsr, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x46
+    op(Eor, AddrMod::DirectIndirectLong, Logical, N | Z, 6), // 0x47
+    op(Pha, AddrMod::Implied, Stack, 0, 3),         // 0x48
+    op(Eor, AddrMod::Immediate, Logical, N | Z, 2), // 0x49
+    op(Lsr, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x4A
+    op(Phk, AddrMod::Implied, Stack, 0, 3),         // 0x4B
+    op(Jmp, AddrMod::Absolute, JumpCall, 0, 3),     // 0x4C
+    op(Eor, AddrMod::Absolute, Logical, N | Z, 4),  // 0x4D
+    op(Lsr, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x4E
+    op(Eor, AddrMod::AbsoluteLong, Logical, N | Z, 5), // 0x4F
+    op(Bvc, AddrMod::Relative, Branch, 0, 2),       // 0x50
+    op(Eor, AddrMod::IndirectY, Logical, N | Z, 5), // 0x51
+    op(Eor, AddrMod::ZeroPageIndirect, Logical, N | Z, 5), // 0x52
+    op(Eor, AddrMod::StackRelativeIndirectY, Logical, N | Z, 7), // 0x53
+    op(Mvn, AddrMod::BlockMove, LoadStore, 0, 7),   // 0x54
+    op(Eor, AddrMod::ZeroPageX, Logical, N | Z, 4), // 0x55
+    op(Lsr, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x56
+    op(Eor, AddrMod::DirectIndirectLongY, Logical, N | Z, 6), // 0x57
+    op(Cli, AddrMod::Implied, StatusCtrl, I, 2),    // 0x58
+    op(Eor, AddrMod::AbsoluteY, Logical, N | Z, 4), // 0x59
+    op(Phy, AddrMod::Implied, Stack, 0, 3),         // 0x5A
+    op(Tcd, AddrMod::Implied, Register, N | Z, 2),  // 0x5B
+    op(Jml, AddrMod::AbsoluteLong, JumpCall, 0, 4), // 0x5C
+    op(Eor, AddrMod::AbsoluteX, Logical, N | Z, 4), // 0x5D
+    op(Lsr, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x5E
+    op(Eor, AddrMod::AbsoluteLongX, Logical, N | Z, 5), // 0x5F
+    op(Rts, AddrMod::Implied, JumpCall, 0, 6),      // 0x60
+    op(Adc, AddrMod::IndirectX, Arithmetic, N | V | Z | C, 6), // 0x61
+    op(Per, AddrMod::RelativeLong, Stack, 0, 6),    // 0x62
+    op(Adc, AddrMod::StackRelative, Arithmetic, N | V | Z | C, 4), // 0x63
+    op(Stz, AddrMod::ZeroPage, LoadStore, 0, 3),    // 0x64
+    op(Adc, AddrMod::ZeroPage, Arithmetic, N | V | Z | C, 3), // 0x65
+    op(Ror, AddrMod::ZeroPage, Shifts, N | Z | C, 5), // 0x66
+    op(
+        Adc,
+        AddrMod::DirectIndirectLong,
+        Arithmetic,
+        N | V | Z | C,
+        6,
+    ), // 0x67
+    op(Pla, AddrMod::Implied, Stack, N | Z, 4),     // 0x68
+    op(Adc, AddrMod::Immediate, Arithmetic, N | V | Z | C, 2), // 0x69
+    op(Ror, AddrMod::Accumulator, Shifts, N | Z | C, 2), // 0x6A
+    op(Rtl, AddrMod::Implied, JumpCall, 0, 6),      // 0x6B
+    op(Jmp, AddrMod::Indirect, JumpCall, 0, 5),     // 0x6C
+    op(Adc, AddrMod::Absolute, Arithmetic, N | V | Z | C, 4), // 0x6D
+    op(Ror, AddrMod::Absolute, Shifts, N | Z | C, 6), // 0x6E
+    op(Adc, AddrMod::AbsoluteLong, Arithmetic, N | V | Z | C, 5), // 0x6F
+    op(Bvs, AddrMod::Relative, Branch, 0, 2),       // 0x70
+    op(Adc, AddrMod::IndirectY, Arithmetic, N | V | Z | C, 5), // 0x71
+    op(Adc, AddrMod::ZeroPageIndirect, Arithmetic, N | V | Z | C, 5), // 0x72
+    op(
+        Adc,
+        AddrMod::StackRelativeIndirectY,
+        Arithmetic,
+        N | V | Z | C,
+        7,
+    ), // 0x73
+    op(Stz, AddrMod::ZeroPageX, LoadStore, 0, 4),   // 0x74
+    op(Adc, AddrMod::ZeroPageX, Arithmetic, N | V | Z | C, 4), // 0x75
+    op(Ror, AddrMod::ZeroPageX, Shifts, N | Z | C, 6), // 0x76
+    op(
+        Adc,
+        AddrMod::DirectIndirectLongY,
+        Arithmetic,
+        N | V | Z | C,
+        6,
+    ), // 0x77
+    op(Sei, AddrMod::Implied, StatusCtrl, I, 2),    // 0x78
+    op(Adc, AddrMod::AbsoluteY, Arithmetic, N | V | Z | C, 4), // 0x79
+    op(Ply, AddrMod::Implied, Stack, N | Z, 4),     // 0x7A
+    op(Tdc, AddrMod::Implied, Register, N | Z, 2),  // 0x7B
+    op(Jmp, AddrMod::AbsoluteIndirectX, JumpCall, 0, 6), // 0x7C
+    op(Adc, AddrMod::AbsoluteX, Arithmetic, N | V | Z | C, 4), // 0x7D
+    op(Ror, AddrMod::AbsoluteX, Shifts, N | Z | C, 7), // 0x7E
+    op(Adc, AddrMod::AbsoluteLongX, Arithmetic, N | V | Z | C, 5), // 0x7F
+    op(Bra, AddrMod::Relative, Branch, 0, 2),       // 0x80
+    op(Sta, AddrMod::IndirectX, LoadStore, 0, 6),   // 0x81
+    op(Brl, AddrMod::RelativeLong, Branch, 0, 4),   // 0x82
+    op(Sta, AddrMod::StackRelative, LoadStore, 0, 4), // 0x83
+    op(Sty, AddrMod::ZeroPage, LoadStore, 0, 3),    // 0x84
+    op(Sta, AddrMod::ZeroPage, LoadStore, 0, 3),    // 0x85
+    op(Stx, AddrMod::ZeroPage, LoadStore, 0, 3),    // 0x86
+    op(Sta, AddrMod::DirectIndirectLong, LoadStore, 0, 6), // 0x87
+    op(Dey, AddrMod::Implied, IncDec, N | Z, 2),    // 0x88
+    op(Bit, AddrMod::Immediate, Logical, N | V | Z, 2), // 0x89
+    op(Txa, AddrMod::Implied, Register, N | Z, 2),  // 0x8A
+    op(Phb, AddrMod::Implied, Stack, 0, 3),         // 0x8B
+    op(Sty, AddrMod::Absolute, LoadStore, 0, 4),    // 0x8C
+    op(Sta, AddrMod::Absolute, LoadStore, 0, 4),    // 0x8D
+    op(Stx, AddrMod::Absolute, LoadStore, 0, 4),    // 0x8E
+    op(Sta, AddrMod::AbsoluteLong, LoadStore, 0, 5), // 0x8F
+    op(Bcc, AddrMod::Relative, Branch, 0, 2),       // 0x90
+    op(Sta, AddrMod::IndirectY, LoadStore, 0, 6),   // 0x91
+    op(Sta, AddrMod::ZeroPageIndirect, LoadStore, 0, 5), // 0x92
+    op(Sta, AddrMod::StackRelativeIndirectY, LoadStore, 0, 7), // 0x93
+    op(Sty, AddrMod::ZeroPageX, LoadStore, 0, 4),   // 0x94
+    op(Sta, AddrMod::ZeroPageX, LoadStore, 0, 4),   // 0x95
+    op(Stx, AddrMod::ZeroPageY, LoadStore, 0, 4),   // 0x96
+    op(Sta, AddrMod::DirectIndirectLongY, LoadStore, 0, 6), // 0x97
+    op(Tya, AddrMod::Implied, Register, N | Z, 2),  // 0x98
+    op(Sta, AddrMod::AbsoluteY, LoadStore, 0, 5),   // 0x99
+    op(Txs, AddrMod::Implied, Stack, 0, 2),         // 0x9A
+    op(Txy, AddrMod::Implied, Register, N | Z, 2),  // 0x9B
+    op(Stz, AddrMod::Absolute, LoadStore, 0, 4),    // 0x9C
+    op(Sta, AddrMod::AbsoluteX, LoadStore, 0, 5),   // 0x9D
+    op(Stz, AddrMod::AbsoluteX, LoadStore, 0, 5),   // 0x9E
+    op(Sta, AddrMod::AbsoluteLongX, LoadStore, 0, 5), // 0x9F
+    op(Ldy, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA0
+    op(Lda, AddrMod::IndirectX, LoadStore, N | Z, 6), // 0xA1
+    op(Ldx, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA2
+    op(Lda, AddrMod::StackRelative, LoadStore, N | Z, 4), // 0xA3
+    op(Ldy, AddrMod::ZeroPage, LoadStore, N | Z, 3), // 0xA4
+    op(Lda, AddrMod::ZeroPage, LoadStore, N | Z, 3), // 0xA5
+    op(Ldx, AddrMod::ZeroPage, LoadStore, N | Z, 3), // 0xA6
+    op(Lda, AddrMod::DirectIndirectLong, LoadStore, N | Z, 6), // 0xA7
+    op(Tay, AddrMod::Implied, Register, N | Z, 2),  // 0xA8
+    op(Lda, AddrMod::Immediate, LoadStore, N | Z, 2), // 0xA9
+    op(Tax, AddrMod::Implied, Register, N | Z, 2),  // 0xAA
+    op(Plb, AddrMod::Implied, Stack, N | Z, 4),     // 0xAB
+    op(Ldy, AddrMod::Absolute, LoadStore, N | Z, 4), // 0xAC
+    op(Lda, AddrMod::Absolute, LoadStore, N | Z, 4), // 0xAD
+    op(Ldx, AddrMod::Absolute, LoadStore, N | Z, 4), // 0xAE
+    op(Lda, AddrMod::AbsoluteLong, LoadStore, N | Z, 5), // 0xAF
+    op(Bcs, AddrMod::Relative, Branch, 0, 2),       // 0xB0
+    op(Lda, AddrMod::IndirectY, LoadStore, N | Z, 5), // 0xB1
+    op(Lda, AddrMod::ZeroPageIndirect, LoadStore, N | Z, 5), // 0xB2
+    op(Lda, AddrMod::StackRelativeIndirectY, LoadStore, N | Z, 7), // 0xB3
+    op(Ldy, AddrMod::ZeroPageX, LoadStore, N | Z, 4), // 0xB4
+    op(Lda, AddrMod::ZeroPageX, LoadStore, N | Z, 4), // 0xB5
+    op(Ldx, AddrMod::ZeroPageY, LoadStore, N | Z, 4), // 0xB6
+    op(Lda, AddrMod::DirectIndirectLongY, LoadStore, N | Z, 6), // 0xB7
+    op(Clv, AddrMod::Implied, StatusCtrl, V, 2),    // 0xB8
+    op(Lda, AddrMod::AbsoluteY, LoadStore, N | Z, 4), // 0xB9
+    op(Tsx, AddrMod::Implied, Stack, N | Z, 2),     // 0xBA
+    op(Tyx, AddrMod::Implied, Register, N | Z, 2),  // 0xBB
+    op(Ldy, AddrMod::AbsoluteX, LoadStore, N | Z, 4), // 0xBC
+    op(Lda, AddrMod::AbsoluteX, LoadStore, N | Z, 4), // 0xBD
+    op(Ldx, AddrMod::AbsoluteY, LoadStore, N | Z, 4), // 0xBE
+    op(Lda, AddrMod::AbsoluteLongX, LoadStore, N | Z, 5), // 0xBF
+    op(Cpy, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xC0
+    op(Cmp, AddrMod::IndirectX, Arithmetic, N | Z | C, 6), // 0xC1
+    op(
+        Rep,
+        AddrMod::Immediate,
+        StatusCtrl,
+        N | V | M | X | D | I | Z | C,
+        3,
+    ), // 0xC2
+    op(Cmp, AddrMod::StackRelative, Arithmetic, N | Z | C, 4), // 0xC3
+    op(Cpy, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xC4
+    op(Cmp, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xC5
+    op(Dec, AddrMod::ZeroPage, IncDec, N | Z, 5),   // 0xC6
+    op(Cmp, AddrMod::DirectIndirectLong, Arithmetic, N | Z | C, 6), // 0xC7
+    op(Iny, AddrMod::Implied, IncDec, N | Z, 2),    // 0xC8
+    op(Cmp, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xC9
+    op(Dex, AddrMod::Implied, IncDec, N | Z, 2),    // 0xCA
+    op(Wai, AddrMod::Implied, SysFun, 0, 3),        // 0xCB
+    op(Cpy, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xCC
+    op(Cmp, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xCD
+    op(Dec, AddrMod::Absolute, IncDec, N | Z, 6),   // 0xCE
+    op(Cmp, AddrMod::AbsoluteLong, Arithmetic, N | Z | C, 5), // 0xCF
+    op(Bne, AddrMod::Relative, Branch, 0, 2),       // 0xD0
+    op(Cmp, AddrMod::IndirectY, Arithmetic, N | Z | C, 5), // 0xD1
+    op(Cmp, AddrMod::ZeroPageIndirect, Arithmetic, N | Z | C, 5), // 0xD2
+    op(
+        Cmp,
+        AddrMod::StackRelativeIndirectY,
+        Arithmetic,
+        N | Z | C,
+        7,
+    ), // 0xD3
+    op(Pei, AddrMod::ZeroPageIndirect, Stack, 0, 6), // 0xD4
+    op(Cmp, AddrMod::ZeroPageX, Arithmetic, N | Z | C, 4), // 0xD5
+    op(Dec, AddrMod::ZeroPageX, IncDec, N | Z, 6),  // 0xD6
+    op(Cmp, AddrMod::DirectIndirectLongY, Arithmetic, N | Z | C, 6), // 0xD7
+    op(Cld, AddrMod::Implied, StatusCtrl, D, 2),    // 0xD8
+    op(Cmp, AddrMod::AbsoluteY, Arithmetic, N | Z | C, 4), // 0xD9
+    op(Phx, AddrMod::Implied, Stack, 0, 3),         // 0xDA
+    op(Stp, AddrMod::Implied, SysFun, 0, 3),        // 0xDB
+    op(Jml, AddrMod::AbsoluteIndirectLong, JumpCall, 0, 6), // 0xDC
+    op(Cmp, AddrMod::AbsoluteX, Arithmetic, N | Z | C, 4), // 0xDD
+    op(Dec, AddrMod::AbsoluteX, IncDec, N | Z, 7),  // 0xDE
+    op(Cmp, AddrMod::AbsoluteLongX, Arithmetic, N | Z | C, 5), // 0xDF
+    op(Cpx, AddrMod::Immediate, Arithmetic, N | Z | C, 2), // 0xE0
+    op(Sbc, AddrMod::IndirectX, Arithmetic, N | V | Z | C, 6), // 0xE1
+    op(
+        Sep,
+        AddrMod::Immediate,
+        StatusCtrl,
+        N | V | M | X | D | I | Z | C,
+        3,
+    ), // 0xE2
+    op(Sbc, AddrMod::StackRelative, Arithmetic, N | V | Z | C, 4), // 0xE3
+    op(Cpx, AddrMod::ZeroPage, Arithmetic, N | Z | C, 3), // 0xE4
+    op(Sbc, AddrMod::ZeroPage, Arithmetic, N | V | Z | C, 3), // 0xE5
+    op(Inc, AddrMod::ZeroPage, IncDec, N | Z, 5),   // 0xE6
+    op(
+        Sbc,
+        AddrMod::DirectIndirectLong,
+        Arithmetic,
+        N | V | Z | C,
+        6,
+    ), // 0xE7
+    op(Inx, AddrMod::Implied, IncDec, N | Z, 2),    // 0xE8
+    op(Sbc, AddrMod::Immediate, Arithmetic, N | V | Z | C, 2), // 0xE9
+    op(Nop, AddrMod::Implied, StatusCtrl, 0, 2),    // 0xEA
+    op(Xba, AddrMod::Implied, Register, N | Z, 3),  // 0xEB
+    op(Cpx, AddrMod::Absolute, Arithmetic, N | Z | C, 4), // 0xEC
+    op(Sbc, AddrMod::Absolute, Arithmetic, N | V | Z | C, 4), // 0xED
+    op(Inc, AddrMod::Absolute, IncDec, N | Z, 6),   // 0xEE
+    op(Sbc, AddrMod::AbsoluteLong, Arithmetic, N | V | Z | C, 5), // 0xEF
+    op(Beq, AddrMod::Relative, Branch, 0, 2),       // 0xF0
+    op(Sbc, AddrMod::IndirectY, Arithmetic, N | V | Z | C, 5), // 0xF1
+    op(Sbc, AddrMod::ZeroPageIndirect, Arithmetic, N | V | Z | C, 5), // 0xF2
+    op(
+        Sbc,
+        AddrMod::StackRelativeIndirectY,
+        Arithmetic,
+        N | V | Z | C,
+        7,
+    ), // 0xF3
+    op(Pea, AddrMod::Absolute, Stack, 0, 5),        // 0xF4
+    op(Sbc, AddrMod::ZeroPageX, Arithmetic, N | V | Z | C, 4), // 0xF5
+    op(Inc, AddrMod::ZeroPageX, IncDec, N | Z, 6),  // 0xF6
+    op(
+        Sbc,
+        AddrMod::DirectIndirectLongY,
+        Arithmetic,
+        N | V | Z | C,
+        6,
+    ), // 0xF7
+    op(Sed, AddrMod::Implied, StatusCtrl, D, 2),    // 0xF8
+    op(Sbc, AddrMod::AbsoluteY, Arithmetic, N | V | Z | C, 4), // 0xF9
+    op(Plx, AddrMod::Implied, Stack, N | Z, 4),     // 0xFA
+    op(Xce, AddrMod::Implied, StatusCtrl, M | X | C, 2), // 0xFB
+    op(Jsr, AddrMod::AbsoluteIndirectX, JumpCall, 0, 8), // 0xFC
+    op(Sbc, AddrMod::AbsoluteX, Arithmetic, N | V | Z | C, 4), // 0xFD
+    op(Inc, AddrMod::AbsoluteX, IncDec, N | Z, 7),  // 0xFE
+    op(Sbc, AddrMod::AbsoluteLongX, Arithmetic, N | V | Z | C, 5), // 0xFF
+];
@@ -0,0 +1,143 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Instructions,
+    Cycles,
+    Timeout,
+    Address,
+    Opcode,
+    LeftProgram,
+    UserBreak,
+}
+
+impl StopReason {
+    pub fn as_str(&self) -> String {
+        let reason = match self {
+            StopReason::Instructions => "instruction limit reached",
+            StopReason::Cycles => "cycle limit reached",
+            StopReason::Timeout => "time limit reached",
+            StopReason::Address => "stop address reached",
+            StopReason::Opcode => "stop opcode reached",
+            StopReason::LeftProgram => "left the program",
+            StopReason::UserBreak => "interrupted by user",
+        };
+        reason.to_string()
+    }
+}
+
+// What a single CPU::step (or the step that ended CPU::run) did. Every
+// outcome carries the PC the step started at and the opcode byte found there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Executed {
+        pc: u16,
+        opcode: u8,
+    },
+    Interrupt {
+        pc: u16,
+        opcode: u8,
+        vector: u16,
+    },
+    Halted {
+        pc: u16,
+        opcode: u8,
+    },
+    Waiting {
+        pc: u16,
+        opcode: u8,
+    },
+    IllegalOpcode {
+        pc: u16,
+        opcode: u8,
+    },
+    Breakpoint {
+        pc: u16,
+        opcode: u8,
+    },
+    BusFault {
+        pc: u16,
+        opcode: u8,
+        addr: u16,
+    },
+    Trapped {
+        pc: u16,
+        opcode: u8,
+    },
+    Stopped {
+        pc: u16,
+        opcode: u8,
+        reason: StopReason,
+    },
+}
+
+impl Outcome {
+    pub fn pc(&self) -> u16 {
+        match *self {
+            Outcome::Executed { pc, .. }
+            | Outcome::Interrupt { pc, .. }
+            | Outcome::Halted { pc, .. }
+            | Outcome::Waiting { pc, .. }
+            | Outcome::IllegalOpcode { pc, .. }
+            | Outcome::Breakpoint { pc, .. }
+            | Outcome::BusFault { pc, .. }
+            | Outcome::Trapped { pc, .. }
+            | Outcome::Stopped { pc, .. } => pc,
+        }
+    }
+
+    pub fn opcode(&self) -> u8 {
+        match *self {
+            Outcome::Executed { opcode, .. }
+            | Outcome::Interrupt { opcode, .. }
+            | Outcome::Halted { opcode, .. }
+            | Outcome::Waiting { opcode, .. }
+            | Outcome::IllegalOpcode { opcode, .. }
+            | Outcome::Breakpoint { opcode, .. }
+            | Outcome::BusFault { opcode, .. }
+            | Outcome::Trapped { opcode, .. }
+            | Outcome::Stopped { opcode, .. } => opcode,
+        }
+    }
+
+    // Whether execution can carry on after this outcome
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Outcome::Executed { .. } | Outcome::Interrupt { .. })
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Outcome::Executed { pc, opcode } => {
+                write!(f, "Executed 0x{:0>2x} at 0x{:0>4x}", opcode, pc)
+            }
+            Outcome::Interrupt { pc, vector, .. } => write!(
+                f,
+                "Interrupt at 0x{:0>4x} through vector 0x{:0>4x}",
+                pc, vector
+            ),
+            Outcome::Halted { pc, opcode } => {
+                write!(f, "Halted on 0x{:0>2x} at 0x{:0>4x}", opcode, pc)
+            }
+            Outcome::Waiting { pc, .. } => {
+                write!(f, "Waiting for an interrupt at 0x{:0>4x}", pc)
+            }
+            Outcome::IllegalOpcode { pc, opcode } => {
+                write!(f, "Illegal opcode 0x{:0>2x} at 0x{:0>4x}", opcode, pc)
+            }
+            Outcome::Breakpoint { pc, .. } => write!(f, "Breakpoint at 0x{:0>4x}", pc),
+            Outcome::BusFault { pc, opcode, addr } => write!(
+                f,
+                "Bus fault reading 0x{:0>4x} (opcode 0x{:0>2x} at 0x{:0>4x})",
+                addr, opcode, pc
+            ),
+            Outcome::Trapped { pc, opcode } => {
+                write!(f, "Trapped on 0x{:0>2x} at 0x{:0>4x}", opcode, pc)
+            }
+            Outcome::Stopped { pc, reason, .. } => {
+                write!(f, "Stopped at 0x{:0>4x}, {}", pc, reason.as_str())
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::memory::Device;
+
+// The 6510 on-chip I/O port. $00 is the data direction register, a set bit
+// makes the pin an output. $01 is the data register, pins set as inputs read
+// back whatever drives them from outside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessorPort {
+    pub ddr: u8,
+    pub data: u8,
+    pub input: u8,
+}
+
+impl ProcessorPort {
+    pub fn new() -> ProcessorPort {
+        // Every pin starts as an input, floating inputs are pulled high
+        ProcessorPort {
+            ddr: 0x00,
+            data: 0x00,
+            input: 0xff,
+        }
+    }
+
+    pub fn pins(&self) -> u8 {
+        (self.data & self.ddr) | (self.input & !self.ddr)
+    }
+}
+
+impl Default for ProcessorPort {
+    fn default() -> ProcessorPort {
+        ProcessorPort::new()
+    }
+}
+
+impl Device for ProcessorPort {
+    fn read(&mut self, offset: u16) -> u8 {
+        self.peek(offset)
+    }
+
+    fn write(&mut self, offset: u16, val: u8) {
+        match offset {
+            0 => self.ddr = val,
+            _ => self.data = val,
+        }
+    }
+
+    fn peek(&self, offset: u16) -> u8 {
+        match offset {
+            0 => self.ddr,
+            _ => self.pins(),
+        }
+    }
+}
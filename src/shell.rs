@@ -1,23 +1,79 @@
+use crate::variant::Variant;
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set by Ctrl-C while a program is running, polled by CPU::run
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+pub static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+mod sigint {
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: i32 = 2;
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        fn _exit(status: i32) -> !;
+    }
+
+    extern "C" fn handler(_: i32) {
+        // Ctrl-C pauses a running program, at the prompt it quits as usual
+        if super::RUNNING.load(Ordering::Relaxed) {
+            super::INTERRUPTED.store(true, Ordering::Relaxed);
+        } else {
+            unsafe { _exit(130) }
+        }
+    }
+
+    pub fn install() {
+        unsafe {
+            signal(SIGINT, handler);
+        }
+    }
+}
+
+pub fn install_interrupt_handler() {
+    #[cfg(unix)]
+    sigint::install();
+}
+
+pub fn set_running(running: bool) {
+    INTERRUPTED.store(false, Ordering::Relaxed);
+    RUNNING.store(running, Ordering::Relaxed);
+}
 
 pub fn welcome() {
     println!("emu6502 Emulator, Disassembler and Debugger");
 }
 
 pub fn prompt(buf: &mut String, msg: Option<&str>) -> usize {
-    if msg.is_none() {
-        print!("$(emu6502)> ");
-    } else {
-        print!("{}", msg.unwrap());
+    match msg {
+        Some(msg) => print!("{}", msg),
+        None => print!("$(emu6502)> "),
     }
     io::stdout().flush().expect("Can't write to the shell");
     io::stdin().read_line(buf).unwrap()
 }
 
-pub fn inp<'a>(inputs: &'a Vec<&str>, loc: usize) -> &'a str {
+pub fn inp<'a>(inputs: &'a [&str], loc: usize) -> &'a str {
     if loc >= inputs.len() {
         ""
     } else {
         inputs[loc].trim()
     }
 }
+
+pub fn parse_addr(inp: &str) -> Option<u16> {
+    let inp = inp.trim();
+    let hex = inp
+        .strip_prefix('$')
+        .or_else(|| inp.strip_prefix("0x"))
+        .unwrap_or(inp);
+    u16::from_str_radix(hex, 16).ok()
+}
+
+pub fn variant_names() -> String {
+    let names: Vec<&str> = Variant::ALL.iter().map(|variant| variant.name()).collect();
+    names.join("|")
+}
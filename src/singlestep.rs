@@ -0,0 +1,453 @@
+use crate::cpu::CPU;
+use crate::flags::{self, Flags};
+use crate::json::Json;
+use crate::memory::Bus;
+use crate::optable;
+use crate::variant::Variant;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// Runs the community single step tests (ProcessorTests / SingleStepTests),
+// one JSON file per opcode holding an array of cases. Each case sets up the
+// registers and a few bytes of RAM, executes one instruction and lists the
+// expected final state along with every bus cycle.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusCycle {
+    pub addr: u32,
+    pub val: u8,
+    pub access: Access,
+}
+
+// Sparse RAM that logs every read and write, peeks and pokes go unrecorded
+#[derive(Debug, Default)]
+pub struct RecordingBus {
+    ram: HashMap<u32, u8>,
+    pub activity: Vec<BusCycle>,
+}
+
+impl RecordingBus {
+    pub fn new() -> RecordingBus {
+        RecordingBus::default()
+    }
+
+    // Sets a byte anywhere in the 24 bit space without recording it
+    pub fn set(&mut self, addr: u32, val: u8) {
+        self.ram.insert(addr, val);
+    }
+}
+
+impl Bus for RecordingBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.read_long(addr as u32)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.write_long(addr as u32, val);
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.peek_long(addr as u32)
+    }
+
+    fn poke(&mut self, addr: u16, val: u8) {
+        self.ram.insert(addr as u32, val);
+    }
+
+    fn read_long(&mut self, addr: u32) -> u8 {
+        let val = self.peek_long(addr);
+        self.activity.push(BusCycle {
+            addr,
+            val,
+            access: Access::Read,
+        });
+        val
+    }
+
+    fn write_long(&mut self, addr: u32, val: u8) {
+        self.ram.insert(addr, val);
+        self.activity.push(BusCycle {
+            addr,
+            val,
+            access: Access::Write,
+        });
+    }
+
+    fn peek_long(&self, addr: u32) -> u8 {
+        self.ram.get(&addr).copied().unwrap_or(0x00)
+    }
+}
+
+// Register and RAM contents as the test files give them. The 65C816 files
+// add the bank, direct page and emulation registers and 16 bit A, X, Y, S.
+#[derive(Debug, Clone, PartialEq)]
+pub struct State {
+    pub pc: u16,
+    pub s: u16,
+    pub a: u16,
+    pub x: u16,
+    pub y: u16,
+    pub p: u8,
+    pub dbr: u8,
+    pub d: u16,
+    pub pbr: u8,
+    pub e: bool,
+    pub ram: Vec<(u32, u8)>,
+}
+
+impl State {
+    pub fn from_json(json: &Json) -> Result<State, String> {
+        let field = |name: &str| json.get(name).and_then(Json::as_u64);
+        let register = |name: &str| field(name).ok_or(format!("Missing register {}", name));
+        let mut ram = vec![];
+        for cell in json.get("ram").and_then(Json::as_array).unwrap_or(&[]) {
+            match cell.as_array() {
+                Some([addr, val]) => ram.push((
+                    addr.as_u64().ok_or("Invalid RAM address")? as u32,
+                    val.as_u64().ok_or("Invalid RAM value")? as u8,
+                )),
+                _ => return Err("Invalid RAM entry".to_string()),
+            }
+        }
+
+        Ok(State {
+            pc: register("pc")? as u16,
+            s: register("s")? as u16,
+            a: register("a")? as u16,
+            x: register("x")? as u16,
+            y: register("y")? as u16,
+            p: register("p")? as u8,
+            dbr: field("dbr").unwrap_or(0) as u8,
+            d: field("d").unwrap_or(0) as u16,
+            pbr: field("pbr").unwrap_or(0) as u8,
+            e: field("e").unwrap_or(1) != 0,
+            ram,
+        })
+    }
+
+    pub fn apply(&self, cpu: &mut CPU<RecordingBus>) {
+        cpu.pc = self.pc;
+        (cpu.a, cpu.ah) = (self.a as u8, (self.a >> 8) as u8);
+        (cpu.x, cpu.xh) = (self.x as u8, (self.x >> 8) as u8);
+        (cpu.y, cpu.yh) = (self.y as u8, (self.y >> 8) as u8);
+        cpu.sp = self.s as u8;
+        cpu.flags = Flags::from(self.p);
+        if cpu.variant.is_65816() {
+            cpu.sph = (self.s >> 8) as u8;
+            cpu.dbr = self.dbr;
+            cpu.dp = self.d;
+            cpu.pbr = self.pbr;
+            cpu.emulation = self.e;
+            // Emulation mode pins M and X, native mode has them instead of B
+            cpu.flags.m = self.e || self.p & flags::M != 0;
+            cpu.flags.x = self.e || self.p & flags::X != 0;
+            cpu.flags.b &= self.e;
+        }
+        for (addr, val) in &self.ram {
+            cpu.memory.set(*addr, *val);
+        }
+    }
+
+    // The CPU state in the same shape, reading back the RAM cells of expected
+    pub fn capture<B: Bus>(cpu: &CPU<B>, expected: &State) -> State {
+        let wide = cpu.variant.is_65816();
+        let word = |lo: u8, hi: u8| {
+            if wide {
+                u16::from_le_bytes([lo, hi])
+            } else {
+                lo as u16
+            }
+        };
+        State {
+            pc: cpu.pc,
+            s: word(cpu.sp, cpu.sph),
+            a: word(cpu.a, cpu.ah),
+            x: word(cpu.x, cpu.xh),
+            y: word(cpu.y, cpu.yh),
+            p: if wide { cpu.status816() } else { cpu.status() },
+            dbr: cpu.dbr,
+            d: cpu.dp,
+            pbr: cpu.pbr,
+            e: cpu.emulation,
+            ram: expected
+                .ram
+                .iter()
+                .map(|(addr, _)| (*addr, cpu.memory.peek_long(*addr)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub name: String,
+    pub initial: State,
+    pub expected: State,
+    // Address, value and kind of every bus cycle. Only the 6502 and 65C02
+    // files use plain read/write kinds, other kinds just count as cycles.
+    pub cycles: Vec<(u32, Option<u8>, String)>,
+}
+
+impl Case {
+    pub fn from_json(json: &Json) -> Result<Case, String> {
+        let mut cycles = vec![];
+        for cycle in json.get("cycles").and_then(Json::as_array).unwrap_or(&[]) {
+            match cycle.as_array() {
+                Some([addr, val, kind]) => cycles.push((
+                    addr.as_u64().ok_or("Invalid cycle address")? as u32,
+                    val.as_u64().map(|val| val as u8),
+                    kind.as_str().unwrap_or("").to_string(),
+                )),
+                _ => return Err("Invalid cycle entry".to_string()),
+            }
+        }
+
+        Ok(Case {
+            name: json
+                .get("name")
+                .and_then(Json::as_str)
+                .unwrap_or("")
+                .to_string(),
+            initial: State::from_json(json.get("initial").ok_or("Missing initial state")?)?,
+            expected: State::from_json(json.get("final").ok_or("Missing final state")?)?,
+            cycles,
+        })
+    }
+
+    // Runs one instruction and lists every difference from the expected state
+    pub fn run(&self, variant: Variant) -> Vec<Mismatch> {
+        let mut cpu = CPU::with_bus(RecordingBus::new(), variant, &[], 0x0000);
+        // Only the instruction's own cycles count, not the reset's
+        cpu.memory.activity.clear();
+        self.initial.apply(&mut cpu);
+        let start_cyc = cpu.cyc;
+        cpu.step();
+
+        let mut mismatches = vec![];
+        let got = State::capture(&cpu, &self.expected);
+        let want = &self.expected;
+        // B and bit 5 only exist on the stack copy of P, unless M and X sit there
+        let p_mask = if variant.is_65816() && !want.e {
+            0xff
+        } else {
+            !(flags::B | flags::UNUSED)
+        };
+        let mut registers = vec![
+            ("PC", want.pc, got.pc),
+            ("S", want.s, got.s),
+            ("A", want.a, got.a),
+            ("X", want.x, got.x),
+            ("Y", want.y, got.y),
+            ("P", (want.p & p_mask) as u16, (got.p & p_mask) as u16),
+        ];
+        if variant.is_65816() {
+            registers.extend([
+                ("DBR", want.dbr as u16, got.dbr as u16),
+                ("D", want.d, got.d),
+                ("PBR", want.pbr as u16, got.pbr as u16),
+                ("E", want.e as u16, got.e as u16),
+            ]);
+        }
+        for (name, want, got) in registers {
+            if want != got {
+                mismatches.push(Mismatch::Register { name, want, got });
+            }
+        }
+        for ((addr, want), (_, got)) in want.ram.iter().zip(&got.ram) {
+            if want != got {
+                mismatches.push(Mismatch::Ram {
+                    addr: *addr,
+                    want: *want,
+                    got: *got,
+                });
+            }
+        }
+
+        let cycles = cpu.cyc - start_cyc;
+        if cycles != self.cycles.len() as u64 {
+            mismatches.push(Mismatch::Cycles {
+                want: self.cycles.len() as u64,
+                got: cycles,
+            });
+        }
+
+        let plain = self
+            .cycles
+            .iter()
+            .all(|(_, _, kind)| kind == "read" || kind == "write");
+        if plain {
+            let activity = &cpu.memory.activity;
+            let differs = activity.len() != self.cycles.len()
+                || self
+                    .cycles
+                    .iter()
+                    .zip(activity)
+                    .any(|((addr, val, kind), cycle)| {
+                        let access = if kind == "write" {
+                            Access::Write
+                        } else {
+                            Access::Read
+                        };
+                        *addr != cycle.addr || *val != Some(cycle.val) || access != cycle.access
+                    });
+            if differs {
+                mismatches.push(Mismatch::Bus {
+                    want: self.cycles.len(),
+                    got: activity.len(),
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    Register {
+        name: &'static str,
+        want: u16,
+        got: u16,
+    },
+    Ram {
+        addr: u32,
+        want: u8,
+        got: u8,
+    },
+    Cycles {
+        want: u64,
+        got: u64,
+    },
+    // Bus activity differs somewhere, with the number of accesses on each side
+    Bus {
+        want: usize,
+        got: usize,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Mismatch::Register { name, want, got } => {
+                write!(f, "{} 0x{:0>2x} instead of 0x{:0>2x}", name, got, want)
+            }
+            Mismatch::Ram { addr, want, got } => write!(
+                f,
+                "RAM 0x{:0>4x} holds 0x{:0>2x} instead of 0x{:0>2x}",
+                addr, got, want
+            ),
+            Mismatch::Cycles { want, got } => {
+                write!(f, "{} cycles instead of {}", got, want)
+            }
+            Mismatch::Bus { want, got } => write!(
+                f,
+                "bus activity differs ({} accesses, expected {})",
+                got, want
+            ),
+        }
+    }
+}
+
+// Mismatch counts for every case of one opcode. A case can fail more than
+// one way, its first failure is kept to show what went wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpcodeReport {
+    pub opcode: u8,
+    pub variant: Variant,
+    pub cases: usize,
+    pub state: usize,
+    pub cycles: usize,
+    pub bus: usize,
+    pub first_failure: Option<(String, Vec<Mismatch>)>,
+}
+
+impl OpcodeReport {
+    pub fn passed(&self) -> bool {
+        self.state == 0 && self.cycles == 0 && self.bus == 0
+    }
+}
+
+impl fmt::Display for OpcodeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = optable::lookup(self.variant, self.opcode).mnemonic;
+        write!(
+            f,
+            "0x{:0>2x} {:<4}: {} cases, {} state, {} timing and {} bus mismatches",
+            self.opcode, mnemonic, self.cases, self.state, self.cycles, self.bus
+        )?;
+        if let Some((name, mismatches)) = &self.first_failure {
+            let details: Vec<String> = mismatches.iter().map(|m| m.to_string()).collect();
+            write!(
+                f,
+                "\n    first failure \"{}\": {}",
+                name,
+                details.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Files are named after their opcode in hex, like a9.json or a9.n.json
+fn opcode_of(filepath: &Path) -> Option<u8> {
+    let name = filepath.file_name()?.to_str()?;
+    u8::from_str_radix(name.get(..2)?, 16).ok()
+}
+
+pub fn run_file(filepath: &Path, variant: Variant) -> Result<OpcodeReport, String> {
+    let shown = filepath.display();
+    let opcode = opcode_of(filepath).ok_or(format!("{} is not named after an opcode", shown))?;
+    let text =
+        fs::read_to_string(filepath).map_err(|err| format!("Can't read {}: {}", shown, err))?;
+    let json = Json::parse(&text).map_err(|err| format!("{}: {}", shown, err))?;
+    let cases = json
+        .as_array()
+        .ok_or(format!("{}: expected an array of cases", shown))?;
+
+    let mut report = OpcodeReport {
+        opcode,
+        variant,
+        cases: cases.len(),
+        state: 0,
+        cycles: 0,
+        bus: 0,
+        first_failure: None,
+    };
+    for case in cases {
+        let case = Case::from_json(case).map_err(|err| format!("{}: {}", shown, err))?;
+        let mismatches = case.run(variant);
+        if mismatches.is_empty() {
+            continue;
+        }
+        let count = |kind: fn(&Mismatch) -> bool| mismatches.iter().any(kind) as usize;
+        report.state += count(|m| matches!(m, Mismatch::Register { .. } | Mismatch::Ram { .. }));
+        report.cycles += count(|m| matches!(m, Mismatch::Cycles { .. }));
+        report.bus += count(|m| matches!(m, Mismatch::Bus { .. }));
+        report.first_failure.get_or_insert((case.name, mismatches));
+    }
+    Ok(report)
+}
+
+// Every opcode file in a directory, in file name order
+pub fn run_dir(dir: &Path, variant: Variant) -> Result<Vec<OpcodeReport>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("Can't read {}: {}", dir.display(), err))?;
+    let mut filepaths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|filepath| {
+            filepath.extension().is_some_and(|ext| ext == "json") && opcode_of(filepath).is_some()
+        })
+        .collect();
+    filepaths.sort();
+    filepaths
+        .iter()
+        .map(|filepath| run_file(filepath, variant))
+        .collect()
+}
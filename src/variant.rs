@@ -0,0 +1,132 @@
+use crate::mnemonic::Mnemonic;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+    Nmos6502,
+    // 6502 in a 28 pin package with a 13 bit address bus (Atari 2600)
+    Mos6507,
+    // 6502 with an I/O port at $00/$01 (Commodore 64)
+    Mos6510,
+    // 6502 core with decimal mode disconnected (NES)
+    Ricoh2A03,
+    Wdc65C02,
+    // 16 bit successor with a 24 bit address bus, starts in 6502 emulation
+    Wdc65C816,
+}
+
+// Accumulator and index register widths of a 65C816 in native mode, every
+// other variant (and emulation mode) always runs 8 bit
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Widths {
+    pub wide_a: bool,
+    pub wide_index: bool,
+}
+
+impl Widths {
+    // Width of the data an instruction moves, accumulator instructions follow
+    // M and index register instructions follow X
+    pub fn wide(&self, mnemonic: Mnemonic) -> bool {
+        match mnemonic {
+            Mnemonic::Adc
+            | Mnemonic::And
+            | Mnemonic::Bit
+            | Mnemonic::Cmp
+            | Mnemonic::Eor
+            | Mnemonic::Lda
+            | Mnemonic::Ora
+            | Mnemonic::Sbc
+            | Mnemonic::Sta
+            | Mnemonic::Stz
+            | Mnemonic::Pha
+            | Mnemonic::Pla
+            | Mnemonic::Asl
+            | Mnemonic::Dec
+            | Mnemonic::Inc
+            | Mnemonic::Lsr
+            | Mnemonic::Rol
+            | Mnemonic::Ror
+            | Mnemonic::Trb
+            | Mnemonic::Tsb => self.wide_a,
+            Mnemonic::Cpx
+            | Mnemonic::Cpy
+            | Mnemonic::Ldx
+            | Mnemonic::Ldy
+            | Mnemonic::Stx
+            | Mnemonic::Sty
+            | Mnemonic::Phx
+            | Mnemonic::Phy
+            | Mnemonic::Plx
+            | Mnemonic::Ply => self.wide_index,
+            _ => false,
+        }
+    }
+}
+
+impl Variant {
+    pub const ALL: [Variant; 6] = [
+        Variant::Nmos6502,
+        Variant::Mos6507,
+        Variant::Mos6510,
+        Variant::Ricoh2A03,
+        Variant::Wdc65C02,
+        Variant::Wdc65C816,
+    ];
+
+    pub fn as_str(&self) -> String {
+        let variant = match self {
+            Variant::Nmos6502 => "NMOS 6502",
+            Variant::Mos6507 => "MOS 6507",
+            Variant::Mos6510 => "MOS 6510",
+            Variant::Ricoh2A03 => "Ricoh 2A03",
+            Variant::Wdc65C02 => "WDC 65C02",
+            Variant::Wdc65C816 => "WDC 65C816",
+        };
+        variant.to_string()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Nmos6502 => "6502",
+            Variant::Mos6507 => "6507",
+            Variant::Mos6510 => "6510",
+            Variant::Ricoh2A03 => "2a03",
+            Variant::Wdc65C02 => "65c02",
+            Variant::Wdc65C816 => "65816",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        match name.to_ascii_lowercase().as_str() {
+            "nmos" => Some(Variant::Nmos6502),
+            "cmos" => Some(Variant::Wdc65C02),
+            "nes" => Some(Variant::Ricoh2A03),
+            "65c816" | "816" => Some(Variant::Wdc65C816),
+            name => Variant::ALL.into_iter().find(|v| v.name() == name),
+        }
+    }
+
+    // Uses the 65C02 opcode table, timings and bug fixes
+    pub fn is_cmos(&self) -> bool {
+        *self == Variant::Wdc65C02
+    }
+
+    pub fn is_65816(&self) -> bool {
+        *self == Variant::Wdc65C816
+    }
+
+    pub fn has_decimal(&self) -> bool {
+        *self != Variant::Ricoh2A03
+    }
+
+    // Address lines that leave the chip, higher bits are ignored
+    pub fn address_mask(&self) -> u16 {
+        match self {
+            Variant::Mos6507 => 0x1fff,
+            _ => 0xffff,
+        }
+    }
+
+    pub fn has_processor_port(&self) -> bool {
+        *self == Variant::Mos6510
+    }
+}
@@ -0,0 +1,39 @@
+use emu6502::dormann::{self, Suite};
+use emu6502::limits::RunLimits;
+use emu6502::variant::Variant;
+use std::env;
+use std::path::Path;
+
+// The binaries aren't shipped with the crate, so these only run with
+// --ignored. Point DORMANN_FUNCTIONAL and DORMANN_DECIMAL at them, or drop
+// them into tests/data.
+fn binary(var: &str, default: &str) -> String {
+    let filepath = env::var(var).unwrap_or_else(|_| default.to_string());
+    assert!(
+        Path::new(&filepath).is_file(),
+        "{} not found, set {} to the test binary",
+        filepath,
+        var
+    );
+    filepath
+}
+
+fn run(suite: Suite, filepath: &str) {
+    let report =
+        dormann::run_file(filepath, suite, Variant::Nmos6502, None, &RunLimits::new()).unwrap();
+    assert!(report.passed, "{}", report);
+}
+
+#[test]
+#[ignore = "needs the functional test binary"]
+fn functional_suite_passes() {
+    let filepath = binary("DORMANN_FUNCTIONAL", "tests/data/6502_functional_test.bin");
+    run(Suite::Functional, &filepath);
+}
+
+#[test]
+#[ignore = "needs the decimal test binary"]
+fn decimal_suite_passes() {
+    let filepath = binary("DORMANN_DECIMAL", "tests/data/6502_decimal_test.bin");
+    run(Suite::Decimal, &filepath);
+}
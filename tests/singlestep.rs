@@ -0,0 +1,27 @@
+use emu6502::singlestep;
+use emu6502::variant::Variant;
+use std::path::Path;
+
+#[test]
+fn lda_immediate_sample_passes() {
+    let report = singlestep::run_file(
+        Path::new("tests/data/singlestep/a9.json"),
+        Variant::Nmos6502,
+    )
+    .unwrap();
+    assert_eq!(report.cases, 3);
+    assert_eq!(report.bus, 0, "{}", report);
+    assert!(report.passed(), "{}", report);
+}
+
+#[test]
+fn samples_match_bus_cycle_by_cycle() {
+    // Read-modify-write, JSR and a taken branch crossing a page
+    let reports =
+        singlestep::run_dir(Path::new("tests/data/singlestep"), Variant::Nmos6502).unwrap();
+    assert_eq!(reports.len(), 4);
+    for report in reports {
+        assert_eq!(report.bus, 0, "{}", report);
+        assert!(report.passed(), "{}", report);
+    }
+}